@@ -0,0 +1,266 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::asset_tag::AssetTag;
+use crate::config::PotConfig;
+use crate::error::PotError;
+use crate::income::Income;
+use crate::lot::Lot;
+use crate::pot::{Pot, Record};
+use crate::sale::Sale;
+use crate::snapshot::Snapshot;
+use crate::ShareCount;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionKind { Split, Rename }
+
+impl ActionKind {
+	pub fn parse(s: &str) -> Option<Self> {
+		match s {
+			"split" => Some(ActionKind::Split),
+			"rename" => Some(ActionKind::Rename),
+			_ => None,
+		}
+	}
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			ActionKind::Split => "split",
+			ActionKind::Rename => "rename",
+		}
+	}
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CorporateAction {
+	pub date: NaiveDate,
+	pub kind: ActionKind,
+	pub symbol: AssetTag,
+	pub ratio: Option<f64>,
+	pub renamed: Option<AssetTag>,
+}
+
+impl Record for CorporateAction {
+	const NAME: &'static str = "actions";
+}
+
+impl CorporateAction {
+	pub fn split(symbol: AssetTag, ratio: f64, date: NaiveDate) -> Self {
+		CorporateAction { date, kind: ActionKind::Split, symbol, ratio: Some(ratio), renamed: None }
+	}
+	pub fn rename(symbol: AssetTag, renamed: AssetTag, date: NaiveDate) -> Self {
+		CorporateAction { date, kind: ActionKind::Rename, symbol, ratio: None, renamed: Some(renamed) }
+	}
+	pub fn describe(&self) -> String {
+		match (self.kind, self.ratio, &self.renamed) {
+			(ActionKind::Split, Some(ratio), _) => format!("split {} by {}", self.symbol.as_str(), ratio),
+			(ActionKind::Rename, _, Some(renamed)) => format!("renamed {} to {}", self.symbol.as_str(), renamed.as_str()),
+			_ => format!("{} {}", self.kind.as_str(), self.symbol.as_str()),
+		}
+	}
+
+	pub fn apply_to_lots(&self, lots: &mut [Lot]) -> usize {
+		let mut changed = 0;
+		for lot in lots.iter_mut().filter(|lot| lot.asset_tag == self.symbol) {
+			match (self.kind, self.ratio, &self.renamed) {
+				(ActionKind::Split, Some(ratio), _) if lot.acquired.iter().all(|acquired| *acquired < self.date) => {
+					lot.share_count = ShareCount(lot.share_count.as_f64() * ratio);
+					changed += 1;
+				}
+				(ActionKind::Rename, _, Some(renamed)) => {
+					lot.asset_tag = renamed.clone();
+					changed += 1;
+				}
+				_ => {}
+			}
+		}
+		changed
+	}
+
+	pub fn apply_to_config(&self, config: &mut PotConfig) {
+		match (self.kind, self.ratio, &self.renamed) {
+			(ActionKind::Split, Some(ratio), _) => {
+				if let Some(quote) = config.prices.quotes.get_mut(&self.symbol) {
+					*quote /= ratio;
+				}
+			}
+			(ActionKind::Rename, _, Some(renamed)) => {
+				let rename = |asset: &mut AssetTag| if asset == &self.symbol { *asset = renamed.clone() };
+				if let Some(quote) = config.prices.quotes.remove(&self.symbol) {
+					config.prices.quotes.insert(renamed.clone(), quote);
+				}
+				if let Some(currency) = config.prices.quote_currencies.remove(&self.symbol) {
+					config.prices.quote_currencies.insert(renamed.clone(), currency);
+				}
+				if let Some(band) = config.rebalance.bands.remove(&self.symbol) {
+					config.rebalance.bands.insert(renamed.clone(), band);
+				}
+				if let Some(substitutes) = config.substitutes.remove(&self.symbol) {
+					config.substitutes.insert(renamed.clone(), substitutes);
+				}
+				if let Some(percent) = config.yields.remove(&self.symbol) {
+					config.yields.insert(renamed.clone(), percent);
+				}
+				if let Some(weight) = config.benchmark.remove(&self.symbol) {
+					config.benchmark.insert(renamed.clone(), weight);
+				}
+				config.substitutes.values_mut().flat_map(|it| it.iter_mut()).for_each(rename);
+				config.groups.values_mut().flat_map(|it| it.targets.iter_mut()).for_each(rename);
+			}
+			_ => {}
+		}
+	}
+}
+
+// Applies the action to every pot in the tree. A rename also rewrites recorded sales,
+// income and snapshots so wash-sale, tax, income and benchmark reports keep matching
+// them to the renamed lots; the original symbol stays in the actions log.
+pub fn apply<P: Pot>(pot: &mut P, action: &CorporateAction) -> Result<usize, PotError> {
+	let mut pots = vec![pot.clone()];
+	pots.extend(pot.read_deep_subpots()?.into_iter().map(|(_, subpot)| *subpot));
+	let mut changed = 0;
+	for mut pot in pots {
+		let mut lots = pot.read_lots()?;
+		let lot_changes = action.apply_to_lots(&mut lots);
+		if lot_changes > 0 {
+			pot.write_lots(&lots)?;
+			changed += lot_changes;
+		}
+		let local_config = pot.read_local_config()?;
+		let mut config = local_config.clone();
+		action.apply_to_config(&mut config);
+		if config != local_config {
+			pot.write_local_config(&config)?;
+		}
+		if let Some(renamed) = &action.renamed {
+			let targets = pot.read_targets()?;
+			if targets.contains(&action.symbol) {
+				let targets = targets.into_iter()
+					.map(|target| if target == action.symbol { renamed.clone() } else { target })
+					.collect::<Vec<_>>();
+				pot.write_targets(&targets)?;
+			}
+			let mut metadata = pot.read_asset_metadata()?;
+			if let Some(categories) = metadata.0.remove(&action.symbol) {
+				metadata.0.insert(renamed.clone(), categories);
+				pot.write_asset_metadata(&metadata)?;
+			}
+			let mut sales = pot.read_records::<Sale>()?;
+			if sales.iter().any(|sale| sale.asset_tag == action.symbol) {
+				for sale in sales.iter_mut().filter(|sale| sale.asset_tag == action.symbol) {
+					sale.asset_tag = renamed.clone();
+				}
+				pot.write_records(&sales)?;
+			}
+			let mut income = pot.read_records::<Income>()?;
+			if income.iter().any(|record| record.asset_tag == action.symbol) {
+				for record in income.iter_mut().filter(|record| record.asset_tag == action.symbol) {
+					record.asset_tag = renamed.clone();
+				}
+				pot.write_records(&income)?;
+			}
+			let mut snapshots = pot.read_records::<Snapshot>()?;
+			if snapshots.iter().any(|snapshot| snapshot.symbol == action.symbol) {
+				for snapshot in snapshots.iter_mut().filter(|snapshot| snapshot.symbol == action.symbol) {
+					snapshot.symbol = renamed.clone();
+				}
+				pot.write_records(&snapshots)?;
+			}
+		}
+	}
+	let mut actions = pot.read_records::<CorporateAction>()?;
+	actions.push(action.clone());
+	pot.write_records(&actions)?;
+	Ok(changed)
+}
+
+pub fn parse_ratio(s: &str) -> Result<f64, PotError> {
+	let invalid = || PotError::invalid_argument("ratio", s);
+	let ratio = match s.split_once([':', '/']) {
+		Some((new, old)) => {
+			let new = new.trim().parse::<f64>().map_err(|_| invalid())?;
+			let old = old.trim().parse::<f64>().map_err(|_| invalid())?;
+			new / old
+		}
+		None => s.trim().parse::<f64>().map_err(|_| invalid())?,
+	};
+	if ratio.is_finite() && ratio > 0.0 { Ok(ratio) } else { Err(invalid()) }
+}
+
+#[cfg(test)]
+mod tests {
+	use chrono::NaiveDate;
+
+	use crate::{Custodian, ShareCount};
+	use crate::action::{apply, CorporateAction, parse_ratio};
+	use crate::asset_tag::AssetTag;
+	use crate::core::PotPath;
+	use crate::income::Income;
+	use crate::lot::Lot;
+	use crate::pot::Pot;
+	use crate::sale::Sale;
+	use crate::snapshot::Snapshot;
+	use crate::testing::temp_pot;
+
+	#[test]
+	fn splits_only_lots_held_before_the_split() {
+		let lot = |uid, acquired| Lot {
+			custodian: Custodian("main".to_string()),
+			asset_tag: AssetTag::equity("nvda"),
+			share_count: ShareCount(10.0),
+			uid,
+			basis: Some(1000.0),
+			acquired: NaiveDate::from_ymd_opt(2024, 6, acquired),
+		};
+		let mut lots = [lot(1, 1), lot(2, 20)];
+		let split = CorporateAction::split(AssetTag::equity("nvda"), parse_ratio("10:1").unwrap(), NaiveDate::from_ymd_opt(2024, 6, 10).unwrap());
+		assert_eq!(split.apply_to_lots(&mut lots), 1);
+		assert_eq!(lots[0].share_count.as_f64(), 100.0);
+		assert_eq!(lots[0].basis, Some(1000.0));
+		assert_eq!(lots[1].share_count.as_f64(), 10.0);
+		assert_eq!(parse_ratio("1:4").unwrap(), 0.25);
+		assert!(parse_ratio("0").is_err());
+	}
+
+	#[test]
+	fn renames_carry_into_recorded_history() {
+		let (fb, meta) = (AssetTag::equity("fb"), AssetTag::equity("meta"));
+		let date = NaiveDate::from_ymd_opt(2022, 6, 9).unwrap();
+		let (_dir, mut pot) = temp_pot("rename");
+		pot.write_records(&[Sale {
+			uid: 1,
+			custodian: Custodian("main".to_string()),
+			asset_tag: fb.clone(),
+			count: 1.0,
+			acquired: None,
+			sold: NaiveDate::from_ymd_opt(2022, 1, 3).unwrap(),
+			proceeds: 300.0,
+			basis: None,
+		}]).unwrap();
+		pot.write_records(&[Income {
+			date: NaiveDate::from_ymd_opt(2022, 3, 15).unwrap(),
+			custodian: Custodian("main".to_string()),
+			asset_tag: fb.clone(),
+			amount: 1.5,
+			reinvested: None,
+		}]).unwrap();
+		let mut config = pot.read_local_config().unwrap();
+		config.yields.insert(fb.clone(), 0.5);
+		config.benchmark.insert(fb.clone(), 0.6);
+		pot.write_local_config(&config).unwrap();
+		pot.write_records(&[Snapshot {
+			date: NaiveDate::from_ymd_opt(2022, 4, 1).unwrap(),
+			pot: PotPath::CurrentFolder,
+			symbol: fb.clone(),
+			shares: 1.0,
+			price: 220.0,
+		}]).unwrap();
+		apply(&mut pot, &CorporateAction::rename(fb.clone(), meta.clone(), date)).unwrap();
+		assert_eq!(pot.read_records::<Sale>().unwrap()[0].asset_tag, meta);
+		assert_eq!(pot.read_records::<Income>().unwrap()[0].asset_tag, meta);
+		assert_eq!(pot.read_local_config().unwrap().yields.get(&meta), Some(&0.5));
+		assert_eq!(pot.read_local_config().unwrap().benchmark.get(&meta), Some(&0.6));
+		assert_eq!(pot.read_records::<Snapshot>().unwrap()[0].symbol, meta);
+		assert_eq!(pot.read_records::<CorporateAction>().unwrap()[0].symbol, fb);
+	}
+}
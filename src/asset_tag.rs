@@ -4,17 +4,65 @@ use std::fmt::Formatter;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use serde::de::Visitor;
 
+use crate::error::PotError;
+
 #[derive(Hash, Ord, PartialOrd, Eq, PartialEq, Clone, Debug)]
 pub enum AssetTag {
 	Equity(String),
 	Pot(String),
-	Usd,
+	Currency(String),
+	Crypto(String),
+	Bond(String),
+	Fund(String),
+	RealEstate(String),
+	Other(String),
+	Group(String),
 }
 
 impl AssetTag {
 	pub fn equity(symbol: &str) -> Self {
 		AssetTag::Equity(symbol.to_uppercase())
 	}
+	pub fn usd() -> Self {
+		AssetTag::currency("USD")
+	}
+	pub fn currency(code: &str) -> Self {
+		let code = code.trim();
+		let code = match code.split_once(':') {
+			Some((namespace, code)) if namespace.eq_ignore_ascii_case("cash") => code,
+			_ => code,
+		};
+		AssetTag::Currency(format!("cash:{}", code.trim().to_uppercase()))
+	}
+	pub fn parse_currency(code: &str) -> Option<Self> {
+		let currency = AssetTag::currency(code);
+		let symbol = currency.symbol();
+		if symbol.len() == 3 && symbol.chars().all(|c| c.is_ascii_alphabetic()) {
+			Some(currency)
+		} else {
+			None
+		}
+	}
+	pub fn crypto(symbol: &str) -> Self {
+		AssetTag::Crypto(format!("crypto:{}", symbol.trim().to_uppercase()))
+	}
+	pub fn bond(cusip: &str) -> Self {
+		AssetTag::Bond(format!("bond:{}", cusip.trim().to_uppercase()))
+	}
+	pub fn fund(symbol: &str) -> Self {
+		AssetTag::Fund(format!("fund:{}", symbol.trim().to_uppercase()))
+	}
+	pub fn real_estate(name: &str) -> Self {
+		AssetTag::RealEstate(format!("realestate:{}", name.trim().to_uppercase()))
+	}
+	pub fn other(name: &str) -> Self {
+		AssetTag::Other(format!("other:{}", name.trim().to_uppercase()))
+	}
+	pub fn group(name: &str) -> Self {
+		let name = name.trim().to_lowercase();
+		let name = if name.starts_with('@') { name } else { format!("@{}", name) };
+		AssetTag::Group(name)
+	}
 	pub fn pot_from_name(name: &str) -> Self {
 		let name = name.trim().to_lowercase();
 		let name = if name.starts_with(":") { name } else { format!(":{}", name) };
@@ -22,48 +70,112 @@ impl AssetTag {
 	}
 
 	pub fn is_subpot(&self) -> bool {
-		match self {
-			AssetTag::Pot(_) => true,
-			_ => false,
-		}
+		matches!(self, AssetTag::Pot(_))
+	}
+	pub fn is_currency(&self) -> bool {
+		matches!(self, AssetTag::Currency(_))
+	}
+	pub fn is_group(&self) -> bool {
+		matches!(self, AssetTag::Group(_))
 	}
 	pub fn is_equity(&self) -> bool {
-		match self {
-			AssetTag::Equity(_) => true,
-			_ => false,
-		}
+		matches!(self, AssetTag::Equity(_))
 	}
 	pub fn as_str(&self) -> &str {
 		match self {
 			AssetTag::Equity(s) => s.as_str(),
 			AssetTag::Pot(s) => s.as_str(),
-			AssetTag::Usd => "USD",
+			AssetTag::Currency(s) => s.as_str(),
+			AssetTag::Crypto(s) => s.as_str(),
+			AssetTag::Bond(s) => s.as_str(),
+			AssetTag::Fund(s) => s.as_str(),
+			AssetTag::RealEstate(s) => s.as_str(),
+			AssetTag::Other(s) => s.as_str(),
+			AssetTag::Group(s) => s.as_str(),
+		}
+	}
+	pub fn class_name(&self) -> &str {
+		match self {
+			AssetTag::Equity(_) => "equity",
+			AssetTag::Pot(_) => "pot",
+			AssetTag::Currency(_) => "cash",
+			AssetTag::Crypto(_) => "crypto",
+			AssetTag::Bond(_) => "bond",
+			AssetTag::Fund(_) => "fund",
+			AssetTag::RealEstate(_) => "realestate",
+			AssetTag::Other(_) => "other",
+			AssetTag::Group(_) => "group",
 		}
 	}
-	pub fn as_folder_name(&self) -> &str {
+	pub fn symbol(&self) -> &str {
+		let s = self.as_str();
+		if self.is_group() {
+			return &s[1..];
+		}
+		match s.find(':') {
+			Some(index) => &s[index + 1..],
+			None => s,
+		}
+	}
+	pub fn provider_symbol(&self) -> Option<String> {
+		match self {
+			AssetTag::Equity(s) => Some(s.to_string()),
+			AssetTag::Fund(_) => Some(self.symbol().to_string()),
+			AssetTag::Crypto(_) => Some(format!("{}-USD", self.symbol())),
+			AssetTag::Currency(_) => Some(format!("{}USD=X", self.symbol())),
+			AssetTag::Pot(_) | AssetTag::Bond(_) | AssetTag::RealEstate(_) | AssetTag::Other(_) | AssetTag::Group(_) => None,
+		}
+	}
+	pub fn share_decimals(&self) -> i32 {
+		match self {
+			AssetTag::Pot(_) | AssetTag::Bond(_) | AssetTag::Group(_) => 0,
+			AssetTag::Currency(_) => 2,
+			AssetTag::Fund(_) => 3,
+			AssetTag::Equity(_) | AssetTag::RealEstate(_) | AssetTag::Other(_) => 4,
+			AssetTag::Crypto(_) => 8,
+		}
+	}
+	pub fn round_shares(&self, count: f64) -> f64 {
+		let scale = 10f64.powi(self.share_decimals());
+		(count * scale).round() / scale
+	}
+	pub fn floor_shares(&self, count: f64) -> f64 {
+		let scale = 10f64.powi(self.share_decimals());
+		(count * scale + 1e-9).floor() / scale
+	}
+	pub fn as_folder_name(&self) -> Result<&str, PotError> {
 		match self {
-			AssetTag::Pot(s) => &s[1..],
-			_ => panic!("Wrong variant")
+			AssetTag::Pot(s) => Ok(&s[1..]),
+			_ => Err(PotError::NotAPot { tag: self.as_str().to_string() })
 		}
 	}
 }
 
-impl ToString for AssetTag {
-	fn to_string(&self) -> String { self.as_str().to_string() }
+impl fmt::Display for AssetTag {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(self.as_str()) }
 }
 
 impl<T: AsRef<str>> From<T> for AssetTag {
 	fn from(t: T) -> Self {
 		let s = t.as_ref().trim();
-		if s.starts_with(":") {
-			AssetTag::pot_from_name(&s[1..])
-		} else {
-			let symbol = s.to_uppercase();
-			if symbol == "USD" {
-				AssetTag::Usd
-			} else {
-				AssetTag::Equity(symbol)
+		if let Some(name) = s.strip_prefix(':') {
+			AssetTag::pot_from_name(name)
+		} else if let Some(name) = s.strip_prefix('@') {
+			AssetTag::group(name)
+		} else if let Some(index) = s.find(':') {
+			let (namespace, symbol) = (&s[..index], &s[index + 1..]);
+			match namespace.to_lowercase().as_str() {
+				"crypto" => AssetTag::crypto(symbol),
+				"bond" => AssetTag::bond(symbol),
+				"fund" => AssetTag::fund(symbol),
+				"realestate" => AssetTag::real_estate(symbol),
+				"other" => AssetTag::other(symbol),
+				"cash" => AssetTag::currency(symbol),
+				"equity" => AssetTag::equity(symbol.trim()),
+				_ => AssetTag::Equity(s.to_uppercase()),
 			}
+		} else {
+			AssetTag::Equity(s.to_uppercase())
 		}
 	}
 }
@@ -99,3 +211,50 @@ impl Serialize for AssetTag {
 		serializer.serialize_str(&s)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+
+	use crate::asset_tag::AssetTag;
+
+	#[test]
+	fn namespaces_round_trip() {
+		let tags = [
+			AssetTag::equity("vti"),
+			AssetTag::currency("eur"),
+			AssetTag::pot_from_name("bonds"),
+			AssetTag::group("us-equity"),
+			AssetTag::crypto("btc"),
+			AssetTag::bond("912828zq6"),
+			AssetTag::fund("vtsax"),
+			AssetTag::real_estate("home"),
+			AssetTag::other("art"),
+		];
+		let map = tags.iter().map(|tag| (tag.clone(), 1.0)).collect::<BTreeMap<_, _>>();
+		let toml = toml::to_string(&map).unwrap();
+		let parsed: BTreeMap<AssetTag, f64> = toml::from_str(&toml).unwrap();
+		assert_eq!(parsed, map);
+		assert_eq!(AssetTag::from("Crypto:btc"), AssetTag::crypto("BTC"));
+		assert_eq!(AssetTag::crypto("btc").as_str(), "crypto:BTC");
+		assert_eq!(AssetTag::crypto("btc").symbol(), "BTC");
+	}
+
+	#[test]
+	fn bare_symbols_stay_equities_and_cash_needs_its_namespace() {
+		assert_eq!(AssetTag::from("NOK"), AssetTag::equity("NOK"));
+		assert_eq!(AssetTag::from("hkd"), AssetTag::equity("HKD"));
+		assert_eq!(AssetTag::from("cash:eur"), AssetTag::currency("EUR"));
+		assert_eq!(AssetTag::currency("EUR").as_str(), "cash:EUR");
+		assert_eq!(AssetTag::currency("cash:EUR").symbol(), "EUR");
+		assert_eq!(AssetTag::parse_currency("nok"), Some(AssetTag::currency("NOK")));
+		assert_eq!(AssetTag::parse_currency("VTSAX"), None);
+	}
+
+	#[test]
+	fn rounds_shares_to_class_precision() {
+		assert_eq!(AssetTag::crypto("BTC").round_shares(0.123456789), 0.12345679);
+		assert_eq!(AssetTag::bond("912828ZQ6").round_shares(2.6), 3.0);
+		assert_eq!(AssetTag::equity("VTI").round_shares(1.23456), 1.2346);
+	}
+}
@@ -1,20 +1,34 @@
-use std::collections::{HashMap, HashSet};
-use std::error::Error;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 
-use smarket::yf::PricingResult;
+use chrono::{Duration, Local, NaiveDate};
+use rand::Rng;
 
+use pot::PotError;
+
+use pot::{AssetTag, copy_pot, Custodian, DeepAsset, FolderPot, Ladder, Lot, Pot, PotPath, Ramp, ReliefMethod, report, ShareCount, SqlitePot, StoredPot};
+use pot::action::{self, CorporateAction};
+use pot::config::{AccountType, CustodianConfig, GoalConfig, GroupConfig, PotConfig};
+use pot::format::{self, FORMAT_VERSION};
+use pot::income::{self, Income, IncomeGrouping};
+use pot::plan::{self, LotPreference};
+use pot::projection::{self, Assumptions, Contribution};
+use pot::date::today;
+use pot::prices::{self, fetch_prices};
+use pot::sqlite_pot::DATABASE_FILE;
+use pot::report::{Grouping, Realized};
+use pot::sale::Term;
+use pot::snapshot::{self, Snapshot};
+use pot::wash::{self, WashSale};
 use table::plain::PlainColumn;
 
-use crate::{Custodian, disk, Lot, print, ShareCount, table};
-use crate::asset_tag::AssetTag;
-use crate::core::{AssetGroup, DeepAsset, into_groups, PotPath, Ramp};
-use crate::pot::{FolderPot, Pot};
-use crate::table::dollar_value::{DollarValueColumn, shorten_abs, shorten_dollars};
+use crate::{print, table};
+use crate::table::dollar_value::{currency_symbol, DollarValueColumn, full_dollars, shorten_abs, shorten_dollars};
 use crate::table::percent::PercentColumn;
 use crate::table::Table;
 
-pub fn init() -> Result<(), Box<dyn Error>> {
-	let mut pot = FolderPot::new();
+pub fn init() -> Result<(), PotError> {
+	let mut pot = StoredPot::open()?;
 	if pot.is_not_initialized() {
 		pot.init()?;
 		println!("Initialized pot in {}", pot.path().display());
@@ -24,41 +38,51 @@ pub fn init() -> Result<(), Box<dyn Error>> {
 	Ok(())
 }
 
-pub fn set_cash(value: f64) -> Result<(), Box<dyn Error>> {
-	let pot = FolderPot::new();
-	pot.write_cash(value)
+pub fn set_cash(value: f64, currency: Option<&str>) -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let currency = match currency {
+		Some(code) => AssetTag::parse_currency(code).ok_or_else(|| PotError::invalid_argument("CURRENCY", code))?,
+		None => pot.read_config()?.base_currency(),
+	};
+	pot.write_cash(&currency, value)
 }
 
-pub fn cash() -> Result<(), Box<dyn Error>> {
-	let pot = FolderPot::new();
-	let cash_value = pot.read_cash()?;
-	println!("${:.2}", cash_value);
+pub fn cash() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	for (currency, amount) in pot.read_cash_balances()? {
+		println!("{:3}  {:.2}", currency.symbol(), amount);
+	}
 	Ok(())
 }
 
-pub fn set_ramp(ramp_s: &str) -> Result<(), Box<dyn Error>> {
-	let ramp = Ramp::from_str(ramp_s);
-	let pot = FolderPot::new();
+pub fn set_ramp(ramp_s: &str) -> Result<(), PotError> {
+	let ramp = Ramp::parse(ramp_s);
+	let pot = StoredPot::open()?;
 	pot.write_ramp(ramp)?;
 	println!("{}", ramp_s);
 	Ok(())
 }
 
-pub fn ramp() -> Result<(), Box<dyn Error>> {
-	let pot = FolderPot::new();
+pub fn ramp() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
 	let ramp = pot.read_ramp()?;
 	println!("{}", ramp.as_str());
 	Ok(())
 }
 
-pub fn targets() -> Result<(), Box<dyn Error>> {
-	let pot = FolderPot::new();
+pub fn targets() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
 	let ladder = pot.read_ladder()?;
 	let (symbols, portions) = {
-		let mut asset_portions = ladder.asset_portions();
-		asset_portions.reverse();
-		let symbols = asset_portions.iter().map(|(asset, _)| asset.to_string()).collect();
-		let portions = asset_portions.iter().map(|(_, portion)| portion.to_owned()).collect();
+		let target_portions = ladder.target_portions();
+		let group_portions = ladder.group_portions();
+		let rows = ladder.rows_descending();
+		let symbols = rows.iter()
+			.map(|(asset, group)| if group.is_some() { format!(" {}", asset.as_str()) } else { asset.to_string() })
+			.collect();
+		let portions = rows.iter()
+			.map(|(asset, _)| target_portions.get(asset).or_else(|| group_portions.get(asset)).cloned().unwrap_or(0.0))
+			.collect();
 		(symbols, portions)
 	};
 	let table = Table::new(vec![
@@ -71,12 +95,12 @@ pub fn targets() -> Result<(), Box<dyn Error>> {
 	Ok(())
 }
 
-pub fn add_targets(symbols: &str) -> Result<(), Box<dyn Error>> {
+pub fn add_targets(symbols: &str) -> Result<(), PotError> {
 	let asset_tags = symbols
 		.split(",")
 		.map(|s| AssetTag::from(s.trim()))
 		.collect::<Vec<_>>();
-	let pot = FolderPot::new();
+	let pot = StoredPot::open()?;
 	let mut targets = pot.read_targets()?;
 	let original = targets.len();
 	asset_tags.iter().rev().for_each(|tag| {
@@ -92,12 +116,12 @@ pub fn add_targets(symbols: &str) -> Result<(), Box<dyn Error>> {
 	Ok(())
 }
 
-pub fn remove_targets(symbols: &str) -> Result<(), Box<dyn Error>> {
+pub fn remove_targets(symbols: &str) -> Result<(), PotError> {
 	let asset_tags = symbols
 		.split(",")
 		.map(|s| AssetTag::from(s.trim()))
 		.collect::<Vec<_>>();
-	let pot = FolderPot::new();
+	let pot = StoredPot::open()?;
 	let mut targets = pot.read_targets()?;
 	let original = targets.len();
 	asset_tags.iter().for_each(|tag| {
@@ -113,71 +137,108 @@ pub fn remove_targets(symbols: &str) -> Result<(), Box<dyn Error>> {
 	Ok(())
 }
 
-pub fn promote_target(symbol: &str) -> Result<(), Box<dyn Error>> {
+pub fn set_group(name: &str, symbols: &str, ramp: Option<&str>) -> Result<(), PotError> {
+	let tag = AssetTag::group(name);
+	let targets = symbols
+		.split(",")
+		.filter(|s| !s.trim().is_empty())
+		.map(|s| AssetTag::from(s.trim()))
+		.collect::<Vec<_>>();
+	let pot = StoredPot::open()?;
+	let mut config = pot.read_local_config()?;
+	let ramp = ramp.map(Ramp::parse);
+	config.groups.insert(tag.symbol().to_string(), GroupConfig { ramp, targets: targets.clone() });
+	pot.write_local_config(&config)?;
+	print::targets(&targets);
+	Ok(())
+}
+
+pub fn promote_target(symbol: &str) -> Result<(), PotError> {
 	let asset = AssetTag::from(symbol);
-	let pot = FolderPot::new();
-	let mut ladder = pot.read_ladder()?;
-	match ladder.promote_target(&asset) {
-		None => {
-			println!("{} is not a pot target", asset.as_str());
-		}
-		Some(position) => {
-			pot.write_targets(&ladder.targets)?;
-			println!("Promoted {} to position {}", asset.as_str(), position);
-		}
+	match move_target(&StoredPot::open()?, &asset, Ladder::promote_target)? {
+		None => println!("{} is not a pot target", asset.as_str()),
+		Some(position) => println!("Promoted {} to position {}", asset.as_str(), position),
 	};
 	Ok(())
 }
 
-pub fn demote_target(symbol: &str) -> Result<(), Box<dyn Error>> {
+pub fn demote_target(symbol: &str) -> Result<(), PotError> {
 	let asset = AssetTag::from(symbol);
-	let pot = FolderPot::new();
-	let mut ladder = pot.read_ladder()?;
-	match ladder.demote_target(&asset) {
-		None => {
-			println!("{} is not a pot target", asset.as_str());
-		}
-		Some(position) => {
-			pot.write_targets(&ladder.targets)?;
-			println!("Demoted {} to position {}", asset.as_str(), position);
-		}
+	match move_target(&StoredPot::open()?, &asset, Ladder::demote_target)? {
+		None => println!("{} is not a pot target", asset.as_str()),
+		Some(position) => println!("Demoted {} to position {}", asset.as_str(), position),
 	};
 	Ok(())
 }
 
-pub fn shares(custodian: &str, symbol: &str, count: Option<f64>) -> Result<(), Box<dyn Error>> {
+pub fn move_target<P: Pot>(pot: &P, asset: &AssetTag, step: fn(&mut Ladder, &AssetTag) -> Option<usize>) -> Result<Option<usize>, PotError> {
+	let mut ladder = Ladder::new(pot.read_targets()?, pot.read_ramp()?);
+	if let Some(position) = step(&mut ladder, asset) {
+		pot.write_targets(&ladder.targets)?;
+		return Ok(Some(position));
+	}
+	let mut config = pot.read_local_config()?;
+	for group in config.groups.values_mut() {
+		let mut ladder = Ladder::new(group.targets.clone(), Ramp::Golden);
+		if let Some(position) = step(&mut ladder, asset) {
+			group.targets = ladder.targets;
+			pot.write_local_config(&config)?;
+			return Ok(Some(position));
+		}
+	}
+	Ok(None)
+}
+
+pub fn shares(custodian: &str, symbol: &str, count: Option<f64>) -> Result<(), PotError> {
 	match count {
 		None => {
-			let pot = FolderPot::new();
-			let count = pot.read_shares(&custodian, &symbol)?;
+			let pot = StoredPot::open()?;
+			pot.read_config()?.check_custodian(custodian)?;
+			let count = pot.read_shares(custodian, symbol)?;
 			println!("{}", count);
 		}
 		Some(count) => {
-			let mut pot = FolderPot::new();
-			let uid = pot.write_shares(&custodian, &symbol, count)?;
+			let mut pot = StoredPot::open()?;
+			pot.read_config()?.check_custodian(custodian)?;
+			let count = AssetTag::from(symbol).round_shares(count);
+			let uid = pot.write_shares(custodian, symbol, count)?;
 			println_uid(uid);
 		}
 	}
 	Ok(())
 }
 
-pub fn add_subpot(name: &str) -> Result<(), Box<dyn Error>> {
-	let pot = FolderPot::new();
-	let mut sub = pot.subpot(name);
+pub fn add_subpot(name: &str) -> Result<(), PotError> {
+	let tag = AssetTag::pot_from_name(name);
+	check_pot_name(&tag)?;
+	let pot = StoredPot::open()?;
+	let mut sub = pot.subpot(tag.as_folder_name()?);
 	sub.init_if_not()?;
 	let lots = pot.read_lots()?;
-	let tag = AssetTag::pot_from_name(name);
 	let position = lots.iter().position(|lot| lot.asset_tag == tag);
 	if position.is_none() {
-		add_lot(tag.as_str(), &tag, 1.0, None)?;
+		add_lot(tag.as_str(), &tag, 1.0, None, None, None)?;
 	} else {
 		print::lots(&lots);
 	}
 	Ok(())
 }
 
-pub fn add_lot(custody: &str, asset_tag: &AssetTag, share_count: f64, uid: Option<u64>) -> Result<(), Box<dyn Error>> {
-	let mut pot = FolderPot::new();
+// Pot paths join sub-pot names with "::", so a name holding ':' would be ambiguous.
+fn check_pot_name(tag: &AssetTag) -> Result<(), PotError> {
+	match tag.as_folder_name() {
+		Ok(name) if name.is_empty() || name.contains(':') => Err(PotError::invalid_argument("NAME", tag.as_str())),
+		_ => Ok(()),
+	}
+}
+
+pub fn add_lot(
+	custody: &str, asset_tag: &AssetTag, share_count: f64, uid: Option<u64>,
+	basis: Option<f64>, acquired: Option<NaiveDate>,
+) -> Result<(), PotError> {
+	check_pot_name(asset_tag)?;
+	let mut pot = StoredPot::open()?;
+	pot.read_config()?.check_custodian(custody)?;
 	let uid = uid.unwrap_or_else(Lot::random_uid);
 	let mut lots = pot.read_lots()?;
 	let existing = lots.iter().find(|it| it.uid == uid);
@@ -186,30 +247,250 @@ pub fn add_lot(custody: &str, asset_tag: &AssetTag, share_count: f64, uid: Optio
 	} else {
 		let share_count = match asset_tag {
 			AssetTag::Pot(_) => if share_count > 0.0 { 1.0 } else { 0.0 }
-			_ => share_count
+			_ => asset_tag.round_shares(share_count)
 		};
 		let lot = Lot {
 			custodian: Custodian(custody.to_string()),
 			asset_tag: asset_tag.to_owned(),
 			share_count: ShareCount(share_count),
 			uid,
+			basis,
+			acquired,
 		};
 		lots.extend(vec![lot]);
 		pot.write_lots(&lots)?;
 		print::lots(&lots);
+		let config = pot.read_config()?;
+		for wash in report::wash_sales(&pot)?.iter().filter(|wash| wash.replacement_uid == uid) {
+			warn_wash_sale(&config, wash);
+		}
 	}
 	Ok(())
 }
 
-pub fn gather_asset(symbol: &str, dest: &PotPath) -> Result<(), Box<dyn Error>> {
-	let mut dest_pot = FolderPot::from_pot_path(dest);
+fn warn_wash_sale(config: &PotConfig, wash: &WashSale) {
+	eprintln!(
+		"warning: potential wash sale, {} of the loss on {} {} sold {} is disallowed by lot {:016x}",
+		dollars(config, wash.disallowed), wash.sale.count, wash.sale.asset_tag.as_str(), wash.sale.sold, wash.replacement_uid
+	);
+}
+
+pub fn sell(
+	custodian: &str, symbol: &str, count: f64, method: ReliefMethod,
+	price: Option<f64>, date: Option<NaiveDate>,
+) -> Result<(), PotError> {
+	let mut pot = StoredPot::open()?;
+	let config = pot.read_config()?;
+	config.check_custodian(custodian)?;
+	let asset = AssetTag::from(symbol);
+	let price = match price {
+		Some(price) => price,
+		None => fetch_prices(&pot)?.get(&asset).cloned()
+			.ok_or_else(|| PotError::pricing(asset.as_str(), "no price available"))?,
+	};
+	let sales = pot.sell(custodian, &asset, count, method, price, date.unwrap_or_else(today))?;
+	for sale in &sales {
+		let gain = match sale.gain() {
+			Some(gain) => format!("{} {}-term", dollars_delta(&config, gain), sale.term().as_str()),
+			None => "no basis".to_string(),
+		};
+		println!("{:016x}  {:8}  {:>10}  {}", sale.uid, sale.count, dollars(&config, sale.proceeds), gain);
+	}
+	let proceeds: f64 = sales.iter().map(|sale| sale.proceeds).sum();
+	println!("Added {} to free cash", dollars(&config, proceeds));
+	for wash in report::wash_sales(&pot)? {
+		if sales.iter().any(|sale| sale.uid == wash.sale.uid && sale.sold == wash.sale.sold) {
+			warn_wash_sale(&config, &wash);
+		}
+	}
+	Ok(())
+}
+
+pub fn corporate_action(action: CorporateAction) -> Result<(), PotError> {
+	let mut pot = StoredPot::open()?;
+	let changed = action::apply(&mut pot, &action)?;
+	println!("{} {}, {} lots updated", action.date, action.describe(), changed);
+	Ok(())
+}
+
+pub fn history() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	for action in pot.read_records::<CorporateAction>()? {
+		println!("{}  {}", action.date, action.describe());
+	}
+	Ok(())
+}
+
+pub fn add_income(
+	custodian: &str, symbol: &str, amount: f64, date: Option<NaiveDate>,
+	reinvest: bool, price: Option<f64>,
+) -> Result<(), PotError> {
+	let mut pot = StoredPot::open()?;
+	let config = pot.read_config()?;
+	config.check_custodian(custodian)?;
+	let asset = AssetTag::from(symbol);
+	let reinvested = if reinvest {
+		let price = match price {
+			Some(price) => price,
+			None => fetch_prices(&pot)?.get(&asset).cloned()
+				.ok_or_else(|| PotError::pricing(asset.as_str(), "no price available"))?,
+		};
+		Some(asset.round_shares(amount / price))
+	} else {
+		None
+	};
+	pot.record_income(Income {
+		date: date.unwrap_or_else(today),
+		custodian: Custodian(custodian.to_string()),
+		asset_tag: asset.clone(),
+		amount,
+		reinvested,
+	})?;
+	match reinvested {
+		Some(shares) => println!("Reinvested {} in {} shares of {}", dollars(&config, amount), shares, asset.as_str()),
+		None => println!("Added {} to free cash", dollars(&config, amount)),
+	}
+	Ok(())
+}
+
+pub fn income(grouping: IncomeGrouping) -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let config = pot.read_config()?;
+	let totals = income::income_by(&report::deep_income(&pot)?, grouping);
+	let mut total = 0.0;
+	for (name, amount) in totals {
+		total += amount;
+		println!("{:10}  {:>10}", name, dollars(&config, amount));
+	}
+	println!("{:10}  {:>10}", "total", dollars(&config, total));
+	Ok(())
+}
+
+pub fn projected_income() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let prices = fetch_prices(&pot)?;
+	let config = pot.read_config()?;
+	let decimals = config.display.percent_decimals();
+	let projections = report::projected_income(&pot, &prices, today())?;
+	println!("{:8}  {:>10}  {:>7}  {:>10}  SOURCE", "ASSET ID", "VALUE", "YIELD", "INCOME/YR");
+	let mut total = 0.0;
+	for projection in &projections {
+		total += projection.annual_income;
+		println!(
+			"{:8}  {:>10}  {:6.*}%  {:>10}  {}",
+			projection.asset.as_str(), dollars(&config, projection.market_value),
+			decimals, projection.yield_portion() * 100.0, dollars(&config, projection.annual_income),
+			if projection.manual_yield { "pot.toml" } else { "trailing" }
+		);
+	}
+	println!("Projected annual income: {}", dollars(&config, total));
+	Ok(())
+}
+
+pub fn set_yield(symbol: &str, percent: Option<f64>) -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let mut config = pot.read_local_config()?;
+	match percent {
+		Some(percent) => config.yields.insert(AssetTag::from(symbol), percent),
+		None => config.yields.remove(&AssetTag::from(symbol)),
+	};
+	pot.write_local_config(&config)
+}
+
+pub fn tax_report(year: i32, taxable_only: bool, csv_path: Option<&Path>) -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let config = pot.read_config()?;
+	let symbol = currency_symbol(config.currency());
+	let sales = report::realized_sales(&pot, year, taxable_only)?;
+	if let Some(path) = csv_path {
+		return write_tax_csv(path, &sales);
+	}
+	let term = |term: Term| sales.iter().filter(|it| it.sale.term() == term).collect::<Vec<_>>();
+	let unknown = term(Term::Unknown);
+	if !unknown.is_empty() {
+		eprintln!("warning: {} sales have no acquisition date, so their term is unknown", unknown.len());
+	}
+	let mut parts = vec![
+		("Part I - Short-Term (held one year or less)", term(Term::Short)),
+		("Part II - Long-Term (held more than one year)", term(Term::Long)),
+	];
+	if !unknown.is_empty() {
+		parts.push(("Unknown Term (acquisition date missing)", unknown));
+	}
+	for (index, (title, lines)) in parts.iter().enumerate() {
+		if index > 0 {
+			println!();
+		}
+		print::title(title);
+		println!(
+			"{:20}  {:10}  {:10}  {:10}  {:>12}  {:>12}  {:4}  {:>12}  {:>12}",
+			"DESCRIPTION", "CUSTODY", "ACQUIRED", "SOLD", "PROCEEDS", "BASIS", "CODE", "ADJUSTMENT", "GAIN"
+		);
+		let (mut proceeds, mut basis, mut adjustment, mut gain) = (0.0, 0.0, 0.0, 0.0);
+		for line in lines {
+			let sale = &line.sale;
+			proceeds += sale.proceeds;
+			basis += line.basis().unwrap_or(0.0);
+			adjustment += line.disallowed;
+			gain += line.gain().unwrap_or(0.0);
+			println!(
+				"{:20}  {:10}  {:10}  {:10}  {:>12}  {:>12}  {:4}  {:>12}  {:>12}",
+				format!("{} {}", sale.count, sale.asset_tag.as_str()),
+				sale.custodian.as_str(),
+				sale.acquired.map(|date| date.to_string()).unwrap_or_else(|| "unknown".to_string()),
+				sale.sold,
+				full_dollars(symbol, sale.proceeds),
+				line.basis().map(|basis| full_dollars(symbol, basis)).unwrap_or_else(|| "unknown".to_string()),
+				if line.is_wash_sale() { "W" } else { "" },
+				if line.is_wash_sale() { full_dollars(symbol, line.disallowed) } else { String::new() },
+				line.gain().map(|gain| full_dollars(symbol, gain)).unwrap_or_default(),
+			);
+		}
+		println!(
+			"{:20}  {:10}  {:10}  {:10}  {:>12}  {:>12}  {:4}  {:>12}  {:>12}",
+			"Totals", "", "", "",
+			full_dollars(symbol, proceeds),
+			full_dollars(symbol, basis),
+			"",
+			full_dollars(symbol, adjustment),
+			full_dollars(symbol, gain),
+		);
+	}
+	Ok(())
+}
+
+fn write_tax_csv(path: &Path, sales: &[Realized]) -> Result<(), PotError> {
+	let mut writer = csv::Writer::from_path(path).map_err(|err| PotError::csv(path, err))?;
+	writer.write_record(["term", "description", "custody", "acquired", "sold", "proceeds", "basis", "code", "adjustment", "gain"])
+		.map_err(|err| PotError::csv(path, err))?;
+	for line in sales {
+		let sale = &line.sale;
+		writer.write_record(&[
+			sale.term().as_str().to_string(),
+			format!("{} {}", sale.count, sale.asset_tag.as_str()),
+			sale.custodian.as_str().to_string(),
+			sale.acquired.map(|date| date.to_string()).unwrap_or_default(),
+			sale.sold.to_string(),
+			format!("{:.2}", sale.proceeds),
+			line.basis().map(|basis| format!("{:.2}", basis)).unwrap_or_default(),
+			if line.is_wash_sale() { "W" } else { "" }.to_string(),
+			if line.is_wash_sale() { format!("{:.2}", line.disallowed) } else { String::new() },
+			line.gain().map(|gain| format!("{:.2}", gain)).unwrap_or_default(),
+		]).map_err(|err| PotError::csv(path, err))?;
+	}
+	writer.flush().map_err(|err| PotError::io(path, err))
+}
+
+pub fn gather_asset(symbol: &str, dest: &PotPath) -> Result<(), PotError> {
+	let root = StoredPot::open()?;
+	let mut dest_pot = pot_at(&root, dest);
 	let moving_tag = AssetTag::from(symbol);
-	let moving_assets = FolderPot::new().read_deep_assets()?
+	let moving_assets = root.read_deep_assets()?
 		.into_iter()
 		.filter(|asset| !asset.has_path(dest) && asset.has_tag(&moving_tag))
 		.collect::<Vec<_>>();
 	for ref moving_asset in moving_assets {
-		let mut src_pot = FolderPot::from_pot_path(&moving_asset.pot_path);
+		let mut src_pot = pot_at(&root, &moving_asset.pot_path);
 		let src_lots = src_pot.read_lots()?;
 		let (move_lots, hold_lots): (Vec<Lot>, Vec<Lot>) = src_lots
 			.into_iter()
@@ -221,24 +502,99 @@ pub fn gather_asset(symbol: &str, dest: &PotPath) -> Result<(), Box<dyn Error>>
 	assets()
 }
 
+fn pot_at<P: Pot>(root: &P, pot_path: &PotPath) -> P {
+	pot_path.segment_names()
+		.iter()
+		.filter(|it| !it.is_empty())
+		.fold(root.clone(), |pot, name| *pot.subpot(name))
+}
+
+// The database takes precedence over folder files once it exists, so migrating to
+// sqlite keeps the folder files as a backup and migrating back sets the database aside.
+pub fn migrate(format: &str) -> Result<(), PotError> {
+	let database = Path::new(DATABASE_FILE);
+	match format {
+		"sqlite" => {
+			let src = FolderPot::new();
+			if database.exists() {
+				println!("Skipped migrating: {} already holds a pot", database.display());
+			} else if src.is_not_initialized() {
+				println!("Skipped migrating: no folder pot in {}", src.path().display());
+			} else {
+				copy_pot(&src, &mut SqlitePot::open(database)?)?;
+				println!("Migrated pot in {} to {}", src.path().display(), database.display());
+			}
+		}
+		"folder" => {
+			let src = if database.exists() { Some(SqlitePot::open(database)?) } else { None };
+			match src {
+				Some(src) if !src.is_not_initialized() => {
+					let backup = database.with_extension(format!("sqlite.{}.bak", Local::now().format("%Y%m%d%H%M%S")));
+					if backup.exists() {
+						println!("Skipped migrating: backup {} already exists", backup.display());
+						return Ok(());
+					}
+					let mut dest = FolderPot::new();
+					copy_pot(&src, &mut dest)?;
+					std::fs::rename(database, &backup).map_err(|err| PotError::io(database, err))?;
+					println!("Migrated {} to pot in {}, kept the database as {}", database.display(), dest.path().display(), backup.display());
+				}
+				_ => println!("Skipped migrating: {} holds no pot", database.display()),
+			}
+		}
+		_ => return Err(PotError::invalid_argument("FORMAT", format)),
+	}
+	Ok(())
+}
+pub fn upgrade() -> Result<(), PotError> {
+	let pot = match StoredPot::open()? {
+		StoredPot::Folder(pot) => pot,
+		StoredPot::Sqlite(pot) => {
+			println!("Pot in {} upgrades its schema when opened", pot.path().display());
+			return Ok(());
+		}
+	};
+	let upgraded = format::upgrade(&pot)?;
+	if upgraded.is_empty() {
+		println!("Pot in {} is already at format {}", pot.path().display(), FORMAT_VERSION);
+	}
+	for (pot, version) in upgraded {
+		println!("Upgraded pot in {} from format {} to {}", pot.path().display(), version, FORMAT_VERSION);
+	}
+	Ok(())
+}
+
+pub fn upgrade_if_needed() -> Result<(), PotError> {
+	let pot = match StoredPot::open()? {
+		StoredPot::Folder(pot) => pot,
+		StoredPot::Sqlite(_) => return Ok(()),
+	};
+	if !pot.is_not_initialized() && format::needs_upgrade(&pot)? {
+		for (pot, version) in format::upgrade(&pot)? {
+			eprintln!("Upgraded pot in {} from format {} to {}", pot.path().display(), version, FORMAT_VERSION);
+		}
+	}
+	Ok(())
+}
 
 fn println_uid(uid: u64) {
 	println!("{:016}", uid);
 }
 
-pub fn asset_values() -> Result<(), Box<dyn Error>> {
-	let mut names = Vec::new();
-	let mut values = Vec::new();
-	let pot = FolderPot::new();
+fn uid_list(uids: &[u64]) -> String {
+	uids.iter().map(|uid| format!("{:016x}", uid)).collect::<Vec<_>>().join(", ")
+}
+
+pub fn asset_values() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
 	let prices = fetch_prices(&pot)?;
-	let mut groups: Vec<AssetGroup> = into_groups(pot.read_deep_assets()?).into_iter().collect();
-	groups.sort_by_key(|it| it.tag.to_owned());
-	for group in groups {
-		names.insert(names.len(), group.tag.to_string());
-		values.insert(values.len(), group.market_value(&prices)?);
-	}
+	let (names, values): (Vec<String>, Vec<f64>) = report::asset_values(&pot, &prices)?
+		.into_iter()
+		.map(|(tag, value)| (tag.to_string(), value))
+		.unzip();
 	let asset_col = PlainColumn::from(&names);
-	let values_col = DollarValueColumn::new(&values);
+	let symbol = currency_symbol(pot.read_config()?.currency()).to_string();
+	let values_col = DollarValueColumn::new(&values, &symbol);
 	let table = Table::new(vec![Box::new(asset_col), Box::new(values_col)]);
 	for i in 0..table.lines() {
 		println!("{}", table.printout(i))
@@ -246,99 +602,556 @@ pub fn asset_values() -> Result<(), Box<dyn Error>> {
 	Ok(())
 }
 
-pub fn value(verbose: bool) -> Result<(), Box<dyn Error>> {
-	let pot = FolderPot::new();
+pub fn allocation(key: &str) -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let prices = fetch_prices(&pot)?;
+	let config = pot.read_config()?;
+	let groups = report::allocation(&pot, &prices, key)?;
+	let total: f64 = groups.iter().map(|(_, value)| value).sum();
+	let (names, values): (Vec<String>, Vec<f64>) = groups.into_iter().unzip();
+	let portions = values.iter().map(|value| value / total).collect::<Vec<_>>();
+	let table = Table::new(vec![
+		Box::new(PlainColumn::from(&names)),
+		Box::new(DollarValueColumn::new(&values, currency_symbol(config.currency()))),
+		Box::new(PercentColumn::new(&portions)),
+	]);
+	for i in 0..table.lines() {
+		println!("{}", table.printout(i))
+	}
+	Ok(())
+}
+
+pub fn values_by(key: &str) -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let prices = fetch_prices(&pot)?;
+	let config = pot.read_config()?;
+	let grouping = Grouping::parse(key).ok_or_else(|| PotError::invalid_argument("by", key))?;
+	let values = report::values_by(&pot, &prices, grouping)?;
+	let mut total = 0.0;
+	for (name, value) in values {
+		total += value;
+		println!("{:14}  {:>8}", name, dollars(&config, value));
+	}
+	println!("{:=<24}", "");
+	println!("Total: {}", dollars(&config, total));
+	Ok(())
+}
+
+pub fn location() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let prices = fetch_prices(&pot)?;
+	let config = pot.read_config()?;
+	println!("{:8}  {:8}  {:14}  {:>10}  {:>10}", "POT", "ASSET ID", "ACCOUNT TYPE", "SUGGESTED", "HELD");
+	for placement in report::location(&pot, &prices)? {
+		println!(
+			"{:8}  {:8}  {:14}  {:>10}  {:>10}",
+			placement.pot.as_str(), placement.asset.as_str(), placement.account_type.as_str(),
+			dollars(&config, placement.suggested), dollars(&config, placement.held)
+		);
+	}
+	Ok(())
+}
+
+pub fn add_custodian(id: &str, details: CustodianConfig) -> Result<(), PotError> {
+	if id.trim().is_empty() || id.starts_with(':') {
+		return Err(PotError::invalid_argument("ID", id));
+	}
+	let pot = StoredPot::open()?;
+	let mut config = pot.read_local_config()?;
+	let custodian = config.custodians.entry(id.to_string()).or_default();
+	*custodian = CustodianConfig {
+		name: details.name.or_else(|| custodian.name.clone()),
+		institution: details.institution.or_else(|| custodian.institution.clone()),
+		suffix: details.suffix.or_else(|| custodian.suffix.clone()),
+		account_type: details.account_type.or(custodian.account_type),
+		owner: details.owner.or_else(|| custodian.owner.clone()),
+	};
+	pot.write_local_config(&config)
+}
+
+pub fn parse_account_type(s: &str) -> Result<AccountType, PotError> {
+	AccountType::parse(s).ok_or_else(|| PotError::invalid_argument("type", s))
+}
+
+pub fn custodians() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let prices = fetch_prices(&pot)?;
+	let config = pot.read_config()?;
+	let mut values: BTreeMap<String, f64> = config.custodians.keys().map(|id| (id.to_string(), 0.0)).collect();
+	for (lot, value) in pot.read_portfolio()?.lot_values(&prices)? {
+		if !lot.asset_tag.is_subpot() {
+			*values.entry(lot.custodian.as_str().to_string()).or_insert(0.0) += value;
+		}
+	}
+	println!(
+		"{:10}  {:16}  {:12}  {:8}  {:12}  {:8}  {:>8}",
+		"ID", "NAME", "INSTITUTION", "ACCOUNT", "TYPE", "OWNER", "VALUE"
+	);
+	for (id, value) in values {
+		let (name, institution, suffix, account_type, owner) = match config.custodians.get(&id) {
+			Some(custodian) => (
+				custodian.display_name(&id).to_string(),
+				custodian.institution.clone().unwrap_or_default(),
+				custodian.suffix.as_ref().map(|suffix| format!("...{}", suffix)).unwrap_or_default(),
+				custodian.account_type().as_str().to_string(),
+				custodian.owner.clone().unwrap_or_default(),
+			),
+			None => (id.clone(), String::new(), String::new(), "unregistered".to_string(), String::new()),
+		};
+		println!(
+			"{:10}  {:16}  {:12}  {:8}  {:12}  {:8}  {:>8}",
+			id, name, institution, suffix, account_type, owner, dollars(&config, value)
+		);
+	}
+	Ok(())
+}
+
+pub fn rename_custodian(old: &str, new: &str) -> Result<(), PotError> {
+	if new.trim().is_empty() || new.starts_with(':') {
+		return Err(PotError::invalid_argument("NEW", new));
+	}
+	let renamed = pot::rename_custodian(&mut StoredPot::open()?, old, new)?;
+	println!("Renamed {} to {} on {} lots", old, new, renamed);
+	Ok(())
+}
+
+pub fn value(verbose: bool) -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
 	let prices = fetch_prices(&pot)?;
+	let config = pot.read_config()?;
 	if verbose {
-		let market_values = pot.read_market_values(&prices)?;
-		let mut pairs = market_values.into_iter().collect::<Vec<_>>();
-		pairs.sort_by_key(|x| x.0.to_owned());
+		let pairs = report::market_values(&pot, &prices)?;
 		print::title("Market Values");
 		let mut total = 0.0;
 		for (asset, value) in pairs {
 			total += value;
-			println!("{:8}  {:>8}", asset.as_str(), shorten_dollars(value));
+			println!("{:8}  {:>8}", asset.as_str(), dollars(&config, value));
 		}
 		println!("{:=<18}", "");
-		println!("Total: {}", shorten_dollars(total));
+		println!("Total: {}", dollars(&config, total));
 	} else {
 		let value = pot.read_market_value(&prices)?;
-		println!("{}", shorten_dollars(value));
+		println!("{}", dollars(&config, value));
 	}
 	Ok(())
 }
 
-pub fn status() -> Result<(), Box<dyn Error>> {
-	let pot = FolderPot::new();
-	let ladder = disk::read_ladder(&pot)?;
-	let portfolio = disk::read_portfolio()?;
-	println!("Free Cash: {}", shorten_dollars(portfolio.free_cash));
-	let off_target_symbols = {
-		let mut set = portfolio.symbols().difference(&ladder.target_symbols()).cloned().collect::<HashSet<_>>();
-		set.insert(AssetTag::Usd);
-		set
-	};
-	let portion_targets = {
-		let mut portion_targets = ladder.target_portions();
-		for off_target_symbol in &off_target_symbols {
-			portion_targets.insert(off_target_symbol.clone(), 0.0);
-		}
-		portion_targets
-	};
-	let lot_counts = portfolio.share_counts();
-	let asset_prices = fetch_prices(&pot)?;
-	let mut market_values = portfolio.market_values(&asset_prices);
-	for ref target_symbol in ladder.target_symbols() {
-		if !market_values.contains_key(target_symbol) {
-			market_values.insert(target_symbol.clone(), 0.0);
-		}
-	}
-	let full_value: f64 = market_values.values().sum();
+pub fn status() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let prices = fetch_prices(&pot)?;
+	let status = report::status(&pot, &prices)?;
+	let config = pot.read_config()?;
+	let decimals = config.display.percent_decimals();
+	let symbol = currency_symbol(config.currency());
+	println!("Free Cash: {}", dollars(&config, status.free_cash));
 	println!(
 		"{:8}  {:9}    {:10}  {:^6}    {:^11}  {:^6}    {:10}",
-		"ASSET ID", "SHARES", "MARKET($)", "%PF", "TARGET(%PF)", "$", "ACTION($)"
+		"ASSET ID", "SHARES", format!("MARKET({})", symbol), "%PF", "TARGET(%PF)", symbol, format!("ACTION({})", symbol)
 	);
-	let ordered_symbols = {
-		let mut symbols = ladder.target_symbols_descending();
-		let mut ordered_off_target_symbols = off_target_symbols.iter().cloned().collect::<Vec<_>>();
-		ordered_off_target_symbols.sort();
-		symbols.extend(ordered_off_target_symbols);
-		symbols
-	};
-	for symbol in ordered_symbols {
-		let target_portion = portion_targets.get(&symbol).expect("portion");
-		let count = lot_counts.get(&symbol).cloned().unwrap_or(0.0);
-		let market = market_values.get(&symbol).expect("value").clone();
-		let market_portion = market / full_value;
-		let target = target_portion * full_value;
-		let drift = market - target;
+	for row in &status.rows {
+		let name = if row.group.is_some() { format!(" {}", row.asset.as_str()) } else { row.asset.to_string() };
+		let shares = if row.asset.is_group() { String::new() } else { format!("{:.2}", row.shares) };
 		println!(
-			"{:8}  {:>9.2}    {:>10}  {:5.1}%    {:10.1}%  {:>6}    {:>10}",
-			symbol.as_str(), count,
-			shorten_dollars(market), market_portion * 100.0,
-			target_portion * 100.0, shorten_dollars(target),
-			shorten_dollars_delta(-drift)
+			"{:8}  {:>9}    {:>10}  {:5.*}%    {:10.*}%  {:>6}    {:>10}",
+			name, shares,
+			dollars(&config, row.market_value), decimals, row.market_portion * 100.0,
+			decimals, row.target_portion * 100.0, dollars(&config, row.target_value),
+			if row.in_band { "ok".to_string() } else { dollars_delta(&config, row.action()) }
 		)
 	}
 	// TODO: Display low percentages as <0.1% instead of 0%)
 	Ok(())
 }
 
-pub fn lots() -> Result<(), Box<dyn Error>> {
-	let pot = FolderPot::new();
-	println!("{:16}  {:10}  {:8}  {:8}", "LOT ID", "CUSTODY", "SYMBOL", "COUNT");
+pub fn rebalance() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let prices = fetch_prices(&pot)?;
+	let config = pot.read_config()?;
+	let trades = report::rebalance(&pot, &prices, today())?;
+	if trades.is_empty() {
+		println!("All assets are within their bands");
+		return Ok(());
+	}
+	println!("{:6}  {:8}  {:>10}  {:>10}", "ACTION", "ASSET ID", "SHARES", "VALUE");
+	for trade in &trades {
+		println!(
+			"{:6}  {:8}  {:>10}  {:>10}",
+			if trade.is_buy() { "buy" } else { "sell" },
+			trade.asset.as_str(),
+			if trade.asset.is_subpot() { String::new() } else { trade.shares.abs().to_string() },
+			dollars(&config, trade.value.abs())
+		);
+	}
+	for trade in &trades {
+		match (trade.wash_risk, trade.is_buy()) {
+			(Some(sold), true) => eprintln!(
+				"warning: buying {} within 30 days of its loss sale on {} may be a wash sale", trade.asset.as_str(), sold
+			),
+			(Some(bought), false) => eprintln!(
+				"warning: selling {} at a loss within 30 days of buying it on {} may be a wash sale", trade.asset.as_str(), bought
+			),
+			(None, _) => {}
+		}
+		if !trade.unchecked.is_empty() {
+			eprintln!(
+				"warning: unable to check selling {} for wash sales, lots {} have no acquisition date",
+				trade.asset.as_str(), uid_list(&trade.unchecked)
+			);
+		}
+	}
+	Ok(())
+}
+
+pub fn harvest(threshold: f64) -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let prices = fetch_prices(&pot)?;
+	let config = pot.read_config()?;
+	let date = today();
+	let harvests = report::harvest(&pot, &prices, threshold, date)?;
+	if harvests.is_empty() {
+		println!("No taxable lots with losses above {}", dollars(&config, threshold));
+		return Ok(());
+	}
+	println!(
+		"{:16}  {:8}  {:10}  {:8}  {:>10}  {:>10}  {:>10}  {:>19}  SUBSTITUTES",
+		"LOT ID", "POT", "CUSTODY", "ASSET ID", "SHARES", "VALUE", "LOSS", "DRIFT AFTER SALE"
+	);
+	for harvest in &harvests {
+		let substitutes = harvest.substitutes.iter()
+			.map(|(substitute, drift)| format!("{} ({})", substitute.as_str(), dollars_delta(&config, *drift)))
+			.collect::<Vec<_>>();
+		println!(
+			"{:016x}  {:8}  {:10}  {:8}  {:>10}  {:>10}  {:>10}  {:>19}  {}",
+			harvest.lot.uid, harvest.pot.as_str(), harvest.lot.custodian.as_str(), harvest.lot.asset_tag.as_str(), harvest.lot.share_count.as_f64(),
+			dollars(&config, harvest.value), dollars(&config, harvest.loss),
+			format!("{} -> {}", dollars_delta(&config, harvest.drift), dollars_delta(&config, harvest.drift_after)),
+			if harvest.wash_risk.is_some() { "wait".to_string() } else { substitutes.join(", ") }
+		);
+	}
+	let window = Duration::days(wash::WINDOW_DAYS + 1);
+	for harvest in &harvests {
+		if let Some(bought) = harvest.wash_risk {
+			eprintln!(
+				"warning: selling lot {:016x} now would be a wash sale with the {} bought {}, wait until {}",
+				harvest.lot.uid, harvest.lot.asset_tag.as_str(), bought, bought + window
+			);
+		}
+		if !harvest.unchecked.is_empty() {
+			eprintln!(
+				"warning: unable to check selling lot {:016x} for wash sales, lots {} have no acquisition date",
+				harvest.lot.uid, uid_list(&harvest.unchecked)
+			);
+		}
+	}
+	println!("Substitute drift assumes the proceeds buy that substitute. Do not buy a sold asset back before {}.", date + window);
+	Ok(())
+}
+
+pub fn set_substitutes(symbol: &str, symbols: &str) -> Result<(), PotError> {
+	let substitutes = symbols
+		.split(',')
+		.filter(|s| !s.trim().is_empty())
+		.map(|s| AssetTag::from(s.trim()))
+		.collect::<Vec<_>>();
+	let pot = StoredPot::open()?;
+	let mut config = pot.read_local_config()?;
+	if substitutes.is_empty() {
+		config.substitutes.remove(&AssetTag::from(symbol));
+	} else {
+		config.substitutes.insert(AssetTag::from(symbol), substitutes.clone());
+	}
+	pot.write_local_config(&config)?;
+	print::targets(&substitutes);
+	Ok(())
+}
+
+pub fn deposit(amount: f64, plan: bool, apply_to: Option<&str>) -> Result<(), PotError> {
+	let mut pot = StoredPot::open()?;
+	let config = pot.read_config()?;
+	if let Some(custodian) = apply_to {
+		config.check_custodian(custodian)?;
+	}
+	let base_currency = config.base_currency();
+	pot.write_cash(&base_currency, pot.read_cash(&base_currency)? + amount)?;
+	println!("Added {} to free cash", dollars(&config, amount));
+	if !plan && apply_to.is_none() {
+		return Ok(());
+	}
+	let prices = fetch_prices(&pot)?;
+	let buys = plan::deposit(&pot, &prices, amount)?;
+	println!("{:8}  {:>12}  {:>10}", "ASSET ID", "SHARES", "BUY");
+	for buy in &buys {
+		let shares = buy.shares.map(|shares| shares.to_string()).unwrap_or_default();
+		println!("{:8}  {:>12}  {:>10}", buy.asset.as_str(), shares, dollars(&config, buy.value));
+	}
+	let spent: f64 = buys.iter().map(|buy| buy.value).sum();
+	println!("Left in free cash: {}", dollars(&config, amount - spent));
+	if let Some(custodian) = apply_to {
+		let date = today();
+		let mut lots = Vec::new();
+		for buy in &buys {
+			match buy.shares {
+				Some(shares) => lots.push(Lot {
+					custodian: Custodian(custodian.to_string()),
+					asset_tag: buy.asset.clone(),
+					share_count: ShareCount(shares),
+					uid: Lot::random_uid(),
+					basis: Some(buy.value),
+					acquired: Some(date),
+				}),
+				None => {
+					let subpot = pot.subpot(buy.asset.as_folder_name()?);
+					subpot.write_cash(&base_currency, subpot.read_cash(&base_currency)? + buy.value)?;
+				}
+			}
+		}
+		pot.add_lots(lots)?;
+		pot.write_cash(&base_currency, pot.read_cash(&base_currency)? - spent)?;
+		println!("Applied {} in buys at {}", dollars(&config, spent), custodian);
+	}
+	Ok(())
+}
+
+pub fn withdraw(amount: f64, plan: bool, apply: bool, preference: LotPreference, priority: Option<&str>) -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let config = pot.read_config()?;
+	let base_currency = config.base_currency();
+	if !plan && !apply {
+		let cash = pot.read_cash(&base_currency)?;
+		if cash < amount {
+			return Err(PotError::invalid_argument("amount", &format!("{}, only {} in free cash, see --plan", amount, dollars(&config, cash))));
+		}
+		pot.write_cash(&base_currency, cash - amount)?;
+		println!("Removed {} from free cash", dollars(&config, amount));
+		return Ok(());
+	}
+	let priority = priority.unwrap_or("").split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect::<Vec<_>>();
+	let pots = plan::drain_order(&pot, &priority)?;
+	let prices = fetch_prices(&pot)?;
+	let date = today();
+	let mut remaining = amount;
+	println!("{:10}  {:16}  {:10}  {:8}  {:>10}  {:>10}  {:>10}", "POT", "LOT ID", "CUSTODY", "ASSET ID", "SHARES", "SELL", "GAIN");
+	for (tag, mut pot) in pots {
+		if remaining <= 0.0 {
+			break;
+		}
+		let withdrawal = plan::withdraw(&pot, &prices, remaining, preference, date)?;
+		remaining -= withdrawal.total();
+		let name = if tag.as_str() == ":" { "." } else { tag.as_str() };
+		if withdrawal.cash > 0.0 {
+			println!("{:10}  {:16}  {:10}  {:8}  {:>10}  {:>10}  {:>10}", name, "", "", base_currency.as_str(), "", dollars(&config, withdrawal.cash), "");
+		}
+		for sell in &withdrawal.sells {
+			let gain = match sell.gain {
+				Some(gain) => format!("{}{}", dollars_delta(&config, gain), if sell.long_term { " LT" } else { " ST" }),
+				None => "no basis".to_string(),
+			};
+			println!(
+				"{:10}  {:016x}  {:10}  {:8}  {:>10}  {:>10}  {:>10}",
+				name, sell.lot.uid, sell.lot.custodian.as_str(), sell.lot.asset_tag.as_str(), sell.shares,
+				dollars(&config, sell.value), gain
+			);
+		}
+		if apply {
+			for sell in &withdrawal.sells {
+				let price = sell.value / sell.shares;
+				pot.sell(sell.lot.custodian.as_str(), &sell.lot.asset_tag, sell.shares, ReliefMethod::Specific(sell.lot.uid), price, date)?;
+			}
+			pot.write_cash(&base_currency, pot.read_cash(&base_currency)? - withdrawal.total())?;
+		}
+	}
+	if remaining > 0.005 {
+		println!("Short by {}, the pots do not hold enough", dollars(&config, remaining));
+	}
+	if apply {
+		println!("Withdrew {}", dollars(&config, amount - remaining.max(0.0)));
+	}
+	Ok(())
+}
+
+pub fn project(years: usize, contribution: Contribution, paths: usize, seed: Option<u64>) -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let prices = fetch_prices(&pot)?;
+	let config = pot.read_config()?;
+	let assumptions = Assumptions::read(Path::new("assumptions.toml"))?;
+	let holdings = projection::holdings(&pot, &prices, &assumptions)?;
+	let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+	let outcomes = projection::simulate(&holdings, contribution.monthly(), years, paths, seed);
+	println!("{:8}  {:>10}  {:>7}  {:>7}", "ASSET ID", "VALUE", "RETURN", "VOL");
+	for holding in &holdings {
+		println!(
+			"{:8}  {:>10}  {:6.1}%  {:6.1}%",
+			holding.asset.as_str(), dollars(&config, holding.value),
+			holding.assumption.expected_return, holding.assumption.volatility
+		);
+	}
+	println!();
+	println!("{} paths, seed {}, contributing {} a month", paths, seed, dollars(&config, contribution.monthly()));
+	let percentiles = [10.0, 25.0, 50.0, 75.0, 90.0];
+	println!("{:>4}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}", "YEAR", "P10", "P25", "P50", "P75", "P90");
+	let step = if years <= 10 { 1 } else { 5 };
+	for year in (1..=years).filter(|year| year % step == 0 || *year == years) {
+		let mut values = outcomes.iter().map(|path| path[year - 1]).collect::<Vec<_>>();
+		values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+		let row = percentiles.iter()
+			.map(|percent| format!("{:>10}", dollars(&config, projection::percentile(&values, *percent))))
+			.collect::<Vec<_>>();
+		println!("{:>4}  {}", year, row.join("  "));
+	}
+	Ok(())
+}
+
+pub fn goals() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let prices = fetch_prices(&pot)?;
+	let config = pot.read_config()?;
+	let assumptions = Assumptions::read(Path::new("assumptions.toml"))?;
+	let goals = report::goals(&pot, &prices, &assumptions, today())?;
+	if goals.is_empty() {
+		println!("No goals set, see `pot set goal`");
+		return Ok(());
+	}
+	println!(
+		"{:10}  {:>10}  {:10}  {:>10}  {:>7}  {:>12}  STATUS",
+		"POT", "GOAL", "DATE", "VALUE", "FUNDED", "NEEDED/MO"
+	);
+	for goal in &goals {
+		let status = if goal.value >= goal.goal.amount {
+			"funded"
+		} else if goal.months == 0 {
+			"missed"
+		} else if goal.on_track {
+			"on track"
+		} else {
+			"behind"
+		};
+		println!(
+			"{:10}  {:>10}  {:10}  {:>10}  {:6.1}%  {:>12}  {}",
+			if goal.pot.as_str() == ":" { "." } else { goal.pot.as_str() },
+			dollars(&config, goal.goal.amount), goal.goal.date, dollars(&config, goal.value),
+			goal.funded() * 100.0, dollars(&config, goal.required_monthly), status
+		);
+	}
+	Ok(())
+}
+
+pub fn snapshot(date: NaiveDate) -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let weights = report::benchmark_weights(&pot)?;
+	let prices = prices::fetch_prices_with(&pot, weights.values().flat_map(HashMap::keys).cloned().collect())?;
+	let taken = report::snapshot(&pot, &prices, date)?;
+	let mut snapshots = pot.read_records::<Snapshot>()?;
+	snapshots.retain(|snapshot| snapshot.date != date);
+	snapshots.extend(taken);
+	snapshots.sort_by_key(|snapshot| snapshot.date);
+	pot.write_records(&snapshots)?;
+	println!("Recorded snapshot {}, {} in total", date, snapshot::dates(&snapshots).len());
+	Ok(())
+}
+
+pub fn benchmark() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let config = pot.read_config()?;
+	let decimals = config.display.percent_decimals();
+	let periods = report::benchmark(&pot)?;
+	if periods.is_empty() {
+		println!("Not enough history, record at least two snapshots with `pot snapshot`");
+		return Ok(());
+	}
+	println!("{:10}  {:10}  {:>9}  {:>9}  {:>9}", "FROM", "TO", "POT", "BENCHMARK", "DRIFT");
+	let (mut actual, mut benchmark) = (1.0, 1.0);
+	for period in &periods {
+		println!(
+			"{:10}  {:10}  {:>8.*}%  {:>8.*}%  {:>+8.*}%",
+			period.from, period.to,
+			decimals, period.actual * 100.0, decimals, period.benchmark * 100.0, decimals, period.difference() * 100.0
+		);
+		actual *= 1.0 + period.actual;
+		benchmark *= 1.0 + period.benchmark;
+	}
+	let difference = actual - benchmark;
+	println!(
+		"{:10}  {:10}  {:>8.*}%  {:>8.*}%  {:>+8.*}%",
+		"TOTAL", "",
+		decimals, (actual - 1.0) * 100.0, decimals, (benchmark - 1.0) * 100.0, decimals, difference * 100.0
+	);
+	println!("Drift from the benchmark {} returns", if difference >= 0.0 { "helped" } else { "hurt" });
+	Ok(())
+}
+
+pub fn set_benchmark(weights: &str) -> Result<(), PotError> {
+	let mut benchmark = BTreeMap::new();
+	for pair in weights.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+		let invalid = || PotError::invalid_argument("WEIGHTS", pair);
+		let (symbol, weight) = pair.split_once('=').ok_or_else(invalid)?;
+		let weight = weight.trim().parse::<f64>().map_err(|_| invalid())?;
+		if !weight.is_finite() || weight <= 0.0 {
+			return Err(invalid());
+		}
+		benchmark.insert(AssetTag::from(symbol.trim()), weight);
+	}
+	let pot = StoredPot::open()?;
+	let mut config = pot.read_local_config()?;
+	*config.benchmark = benchmark;
+	pot.write_local_config(&config)
+}
+
+pub fn set_goal(goal: Option<GoalConfig>) -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let mut config = pot.read_local_config()?;
+	*config.goal = goal;
+	pot.write_local_config(&config)
+}
+
+pub fn check() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let prices = fetch_prices(&pot)?;
+	let status = report::status(&pot, &prices)?;
+	let config = pot.read_config()?;
+	let offenders = status.offenders();
+	for row in &offenders {
+		println!(
+			"{:8}  {:>10}  {:>10}",
+			row.asset.as_str(), dollars_delta(&config, row.drift), dollars_delta(&config, row.action())
+		);
+	}
+	if offenders.is_empty() { Ok(()) } else { Err(PotError::OutOfBand { count: offenders.len() }) }
+}
+
+pub fn set_band(symbol: Option<&str>, absolute: Option<f64>, relative: Option<f64>) -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let mut config = pot.read_local_config()?;
+	match symbol {
+		Some(symbol) => {
+			let band = config.rebalance.bands.entry(AssetTag::from(symbol)).or_default();
+			band.absolute = absolute.or(band.absolute);
+			band.relative = relative.or(band.relative);
+		}
+		None => {
+			config.rebalance.absolute = absolute.or(config.rebalance.absolute);
+			config.rebalance.relative = relative.or(config.rebalance.relative);
+		}
+	}
+	pot.write_local_config(&config)
+}
+
+pub fn lots() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	println!("{:16}  {:10}  {:8}  {:8}  {:>10}  {:10}", "LOT ID", "CUSTODY", "SYMBOL", "COUNT", "BASIS", "ACQUIRED");
 	let lots = pot.read_lots()?;
 	for lot in lots {
 		println!(
-			"{:016x}  {:10}  {:8}  {:8}",
-			lot.uid, lot.custodian.as_str(), lot.asset_tag.as_str(), lot.share_count.as_f64()
+			"{:016x}  {:10}  {:8}  {:8}  {:>10}  {:10}",
+			lot.uid, lot.custodian.as_str(), lot.asset_tag.as_str(), lot.share_count.as_f64(),
+			lot.basis.map(|basis| format!("{:.2}", basis)).unwrap_or_default(),
+			lot.acquired.map(|date| date.to_string()).unwrap_or_default()
 		);
 	}
 	Ok(())
 }
 
-pub fn assets() -> Result<(), Box<dyn Error>> {
-	let pot = FolderPot::new();
+pub fn assets() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
 	let deep_assets = pot.read_deep_assets()?;
 	let mut titles = deep_assets.iter().map(DeepAsset::title).collect::<Vec<_>>();
 	titles.sort();
@@ -348,58 +1161,41 @@ pub fn assets() -> Result<(), Box<dyn Error>> {
 	Ok(())
 }
 
-pub fn shorten_dollars_delta(no: f64) -> String {
-	if no.is_nan() {
-		"$NAN".to_string()
+pub fn config() -> Result<(), PotError> {
+	let pot = StoredPot::open()?;
+	let config = pot.read_config()?;
+	let toml = toml::to_string(&config).map_err(|err| PotError::parse(pot.path(), None, err))?;
+	print!("{}", toml);
+	Ok(())
+}
+
+pub fn dollars(config: &PotConfig, no: f64) -> String {
+	let symbol = currency_symbol(config.currency());
+	if config.display.full_dollars() { full_dollars(symbol, no) } else { shorten_dollars(symbol, no) }
+}
+
+pub fn dollars_delta(config: &PotConfig, no: f64) -> String {
+	let symbol = currency_symbol(config.currency());
+	if !config.display.full_dollars() || no.is_nan() {
+		shorten_dollars_delta(symbol, no)
 	} else if no == 0.0 {
-		"=$0".to_string()
+		format!("={}0.00", symbol)
 	} else if no.is_sign_negative() {
-		format!("-${}", shorten_abs(no))
+		format!("-{}{:.2}", symbol, no.abs())
 	} else {
-		format!("+${}", shorten_abs(no))
+		format!("+{}{:.2}", symbol, no)
 	}
 }
 
-
-fn fetch_prices(pot: &impl Pot) -> Result<HashMap<AssetTag, f64>, Box<dyn Error>> {
-	let mut prices = HashMap::new();
-	prices.insert(AssetTag::Usd, 1.0);
-	{
-		let equity_assets = pot.read_deep_lot_assets()?.into_iter().map(|it| it).collect();
-		let equity_prices = fetch_equity_prices(equity_assets)?;
-		prices.extend(equity_prices);
-	};
-	{
-		let mut subpots = pot.read_deep_subpots()?;
-		subpots.reverse();
-		for (asset, subpot) in subpots {
-			let value = subpot.read_market_value(&prices)?;
-			prices.insert(asset, value);
-		}
+pub fn shorten_dollars_delta(symbol: &str, no: f64) -> String {
+	if no.is_nan() {
+		format!("{}NAN", symbol)
+	} else if no == 0.0 {
+		format!("={}0", symbol)
+	} else if no.is_sign_negative() {
+		format!("-{}{}", symbol, shorten_abs(no))
+	} else {
+		format!("+{}{}", symbol, shorten_abs(no))
 	}
-	Ok(prices)
 }
 
-fn fetch_equity_prices(equities: Vec<AssetTag>) -> Result<HashMap<AssetTag, f64>, Box<dyn Error>> {
-	let prices_by_asset = if equities.is_empty() {
-		HashMap::new()
-	} else {
-		let assets_by_symbol = equities
-			.iter()
-			.map(|it| (it.as_str().to_string(), it.clone()))
-			.collect::<HashMap<String, _>>();
-		let symbols = assets_by_symbol.keys().cloned().collect::<Vec<_>>();
-		smarket::yf::price_assets(&symbols)?
-			.iter()
-			.map(|(symbol, result)| {
-				let usd_price = match result {
-					PricingResult::Priced { usd_price, .. } => *usd_price,
-					_ => panic!("missing price")
-				};
-				let asset_tag = assets_by_symbol.get(symbol).expect("asset-tag").to_owned();
-				(asset_tag, usd_price.as_f64())
-			})
-			.collect::<HashMap<AssetTag, _>>()
-	};
-	Ok(prices_by_asset)
-}
\ No newline at end of file
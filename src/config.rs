@@ -0,0 +1,396 @@
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::asset_tag::AssetTag;
+use crate::core::Ramp;
+use crate::error::PotError;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceProvider { Yahoo, Manual }
+
+// A part of pot.toml with its own defaults and its own rule for filling unset
+// values from the parent pot's section.
+pub trait Section: Clone + Default + PartialEq {
+	fn inherit(&self, parent: &Self) -> Self;
+	fn is_empty(&self) -> bool { self == &Self::default() }
+}
+
+impl<T: Clone + PartialEq> Section for Option<T> {
+	fn inherit(&self, parent: &Self) -> Self { self.clone().or_else(|| parent.clone()) }
+}
+
+impl<K: Clone + Ord, V: Clone + PartialEq> Section for BTreeMap<K, V> {
+	fn inherit(&self, parent: &Self) -> Self {
+		let mut merged = parent.clone();
+		merged.extend(self.clone());
+		merged
+	}
+}
+
+// A section that belongs to its own pot and is never filled from the parent's.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Local<T>(pub T);
+
+impl<T: Clone + Default + PartialEq> Section for Local<T> {
+	fn inherit(&self, _parent: &Self) -> Self { self.clone() }
+}
+
+impl<T> Deref for Local<T> {
+	type Target = T;
+	fn deref(&self) -> &T { &self.0 }
+}
+
+impl<T> DerefMut for Local<T> {
+	fn deref_mut(&mut self) -> &mut T { &mut self.0 }
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PotConfig {
+	#[serde(skip_serializing_if = "Section::is_empty")]
+	pub ramp: Option<Ramp>,
+	#[serde(skip_serializing_if = "Section::is_empty")]
+	pub currency: Option<String>,
+	#[serde(skip_serializing_if = "Section::is_empty")]
+	pub prices: PriceConfig,
+	#[serde(skip_serializing_if = "Section::is_empty")]
+	pub groups: BTreeMap<String, GroupConfig>,
+	#[serde(skip_serializing_if = "Section::is_empty")]
+	pub custodians: BTreeMap<String, CustodianConfig>,
+	#[serde(skip_serializing_if = "Section::is_empty")]
+	pub substitutes: BTreeMap<AssetTag, Vec<AssetTag>>,
+	#[serde(skip_serializing_if = "Section::is_empty")]
+	pub yields: BTreeMap<AssetTag, f64>,
+	#[serde(skip_serializing_if = "Section::is_empty")]
+	pub goal: Local<Option<GoalConfig>>,
+	#[serde(skip_serializing_if = "Section::is_empty")]
+	pub benchmark: Local<BTreeMap<AssetTag, f64>>,
+	#[serde(skip_serializing_if = "Section::is_empty")]
+	pub rebalance: RebalanceConfig,
+	#[serde(skip_serializing_if = "Section::is_empty")]
+	pub display: DisplayConfig,
+}
+
+impl PotConfig {
+	pub fn inherit(&self, parent: &PotConfig) -> PotConfig {
+		PotConfig {
+			ramp: self.ramp.inherit(&parent.ramp),
+			currency: self.currency.inherit(&parent.currency),
+			prices: self.prices.inherit(&parent.prices),
+			groups: self.groups.inherit(&parent.groups),
+			custodians: self.custodians.inherit(&parent.custodians),
+			substitutes: self.substitutes.inherit(&parent.substitutes),
+			yields: self.yields.inherit(&parent.yields),
+			goal: self.goal.inherit(&parent.goal),
+			benchmark: self.benchmark.inherit(&parent.benchmark),
+			rebalance: self.rebalance.inherit(&parent.rebalance),
+			display: self.display.inherit(&parent.display),
+		}
+	}
+	pub fn ramp(&self) -> Ramp { self.ramp.unwrap_or(Ramp::Golden) }
+	pub fn currency(&self) -> &str { self.currency.as_deref().unwrap_or("USD") }
+	pub fn base_currency(&self) -> AssetTag { AssetTag::currency(self.currency()) }
+	pub fn group(&self, tag: &AssetTag) -> Option<&GroupConfig> { self.groups.get(tag.symbol()) }
+	pub fn account_type(&self, custodian: &str) -> Option<AccountType> {
+		self.custodians.get(custodian).map(CustodianConfig::account_type)
+	}
+	pub fn check_custodian(&self, custodian: &str) -> Result<(), PotError> {
+		if self.custodians.is_empty() || self.custodians.contains_key(custodian) || custodian.starts_with(':') {
+			Ok(())
+		} else {
+			Err(PotError::UnknownCustodian { custodian: custodian.to_string() })
+		}
+	}
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriceConfig {
+	#[serde(skip_serializing_if = "Section::is_empty")]
+	pub provider: Option<PriceProvider>,
+	#[serde(skip_serializing_if = "Section::is_empty")]
+	pub quotes: BTreeMap<AssetTag, f64>,
+	#[serde(skip_serializing_if = "Section::is_empty")]
+	pub quote_currencies: BTreeMap<AssetTag, String>,
+}
+
+impl Section for PriceConfig {
+	fn inherit(&self, parent: &PriceConfig) -> PriceConfig {
+		PriceConfig {
+			provider: self.provider.inherit(&parent.provider),
+			quotes: self.quotes.inherit(&parent.quotes),
+			quote_currencies: self.quote_currencies.inherit(&parent.quote_currencies),
+		}
+	}
+}
+
+impl PriceConfig {
+	pub fn provider(&self) -> PriceProvider { self.provider.unwrap_or(PriceProvider::Yahoo) }
+	pub fn quote_currency(&self, asset: &AssetTag) -> AssetTag {
+		match asset {
+			AssetTag::Currency(_) => AssetTag::usd(),
+			_ => self.quote_currencies.get(asset).map(|code| AssetTag::currency(code)).unwrap_or_else(AssetTag::usd),
+		}
+	}
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AccountType {
+	Taxable,
+	TaxDeferred,
+	#[serde(alias = "roth")]
+	TaxFree,
+	Hsa,
+}
+
+impl AccountType {
+	pub fn parse(s: &str) -> Option<Self> {
+		match s.trim().to_lowercase().as_str() {
+			"taxable" => Some(AccountType::Taxable),
+			"tax-deferred" | "deferred" | "ira" | "401k" => Some(AccountType::TaxDeferred),
+			"tax-free" | "roth" => Some(AccountType::TaxFree),
+			"hsa" => Some(AccountType::Hsa),
+			_ => None,
+		}
+	}
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			AccountType::Taxable => "taxable",
+			AccountType::TaxDeferred => "tax-deferred",
+			AccountType::TaxFree => "tax-free",
+			AccountType::Hsa => "hsa",
+		}
+	}
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustodianConfig {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub institution: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub suffix: Option<String>,
+	#[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+	pub account_type: Option<AccountType>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub owner: Option<String>,
+}
+
+impl CustodianConfig {
+	pub fn account_type(&self) -> AccountType { self.account_type.unwrap_or(AccountType::Taxable) }
+	pub fn display_name<'a>(&'a self, id: &'a str) -> &'a str { self.name.as_deref().unwrap_or(id) }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GoalConfig {
+	pub amount: f64,
+	pub date: NaiveDate,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub monthly: Option<f64>,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GroupConfig {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub ramp: Option<Ramp>,
+	pub targets: Vec<AssetTag>,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RebalanceConfig {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub absolute: Option<f64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub relative: Option<f64>,
+	#[serde(skip_serializing_if = "BTreeMap::is_empty")]
+	pub bands: BTreeMap<AssetTag, Band>,
+}
+
+impl Section for RebalanceConfig {
+	fn inherit(&self, parent: &RebalanceConfig) -> RebalanceConfig {
+		RebalanceConfig {
+			absolute: self.absolute.inherit(&parent.absolute),
+			relative: self.relative.inherit(&parent.relative),
+			bands: self.bands.inherit(&parent.bands),
+		}
+	}
+}
+
+impl RebalanceConfig {
+	pub fn band(&self, asset: &AssetTag) -> Band {
+		let global = Band { absolute: self.absolute, relative: self.relative };
+		match self.bands.get(asset) {
+			Some(band) => Band { absolute: band.absolute.or(global.absolute), relative: band.relative.or(global.relative) },
+			None => global,
+		}
+	}
+}
+
+// Percent of the target value a band allows when neither limit is configured.
+pub const DEFAULT_RELATIVE_BAND: f64 = 5.0;
+
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Band {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub absolute: Option<f64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub relative: Option<f64>,
+}
+
+impl Band {
+	pub fn contains(&self, drift: f64, target_value: f64) -> bool {
+		let drift = drift.abs();
+		match (self.absolute, self.relative) {
+			(None, None) => drift <= target_value.abs() * DEFAULT_RELATIVE_BAND / 100.0,
+			(absolute, relative) => {
+				absolute.iter().all(|absolute| drift <= *absolute)
+					&& relative.iter().all(|relative| drift <= target_value.abs() * relative / 100.0)
+			}
+		}
+	}
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub full_dollars: Option<bool>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub percent_decimals: Option<usize>,
+}
+
+impl Section for DisplayConfig {
+	fn inherit(&self, parent: &DisplayConfig) -> DisplayConfig {
+		DisplayConfig {
+			full_dollars: self.full_dollars.inherit(&parent.full_dollars),
+			percent_decimals: self.percent_decimals.inherit(&parent.percent_decimals),
+		}
+	}
+}
+
+impl DisplayConfig {
+	pub fn full_dollars(&self) -> bool { self.full_dollars.unwrap_or(false) }
+	pub fn percent_decimals(&self) -> usize { self.percent_decimals.unwrap_or(1) }
+}
+
+#[cfg(test)]
+mod tests {
+	use chrono::NaiveDate;
+
+	use crate::asset_tag::AssetTag;
+	use crate::config::{
+		AccountType, Band, CustodianConfig, DisplayConfig, GoalConfig, Local, PotConfig, PriceConfig, PriceProvider, RebalanceConfig,
+	};
+	use crate::core::Ramp;
+	use crate::error::PotError;
+
+	#[test]
+	fn subpot_config_inherits_unset_values_from_its_parent() {
+		let (vti, bnd) = (AssetTag::from("VTI"), AssetTag::from("BND"));
+		let parent = PotConfig {
+			ramp: Some(Ramp::Flat),
+			currency: Some("EUR".to_string()),
+			prices: PriceConfig {
+				provider: Some(PriceProvider::Manual),
+				quotes: vec![(vti.clone(), 200.0), (bnd.clone(), 70.0)].into_iter().collect(),
+				..PriceConfig::default()
+			},
+			goal: Local(Some(GoalConfig { amount: 1000.0, date: NaiveDate::from_ymd_opt(2030, 1, 1).unwrap(), monthly: None })),
+			benchmark: Local(vec![(vti.clone(), 1.0)].into_iter().collect()),
+			rebalance: RebalanceConfig { relative: Some(5.0), ..RebalanceConfig::default() },
+			display: DisplayConfig { full_dollars: Some(true), percent_decimals: None },
+			..PotConfig::default()
+		};
+		let child = PotConfig {
+			currency: Some("USD".to_string()),
+			prices: PriceConfig { quotes: vec![(vti.clone(), 210.0)].into_iter().collect(), ..PriceConfig::default() },
+			display: DisplayConfig { full_dollars: None, percent_decimals: Some(2) },
+			..PotConfig::default()
+		};
+		let config = child.inherit(&parent);
+		assert_eq!(config.ramp(), Ramp::Flat);
+		assert_eq!(config.currency(), "USD");
+		assert_eq!(config.prices.provider(), PriceProvider::Manual);
+		assert_eq!((config.prices.quotes[&vti], config.prices.quotes[&bnd]), (210.0, 70.0));
+		assert_eq!(config.rebalance.relative, Some(5.0));
+		assert_eq!((config.display.full_dollars(), config.display.percent_decimals()), (true, 2));
+		assert!(config.goal.is_none());
+		assert!(config.benchmark.is_empty());
+	}
+
+	#[test]
+	fn empty_config_uses_defaults() {
+		let config: PotConfig = toml::from_str("").unwrap();
+		assert_eq!(config.ramp(), Ramp::Golden);
+		assert_eq!(config.currency(), "USD");
+		assert_eq!(config.prices.provider(), PriceProvider::Yahoo);
+		assert_eq!(toml::to_string(&config).unwrap(), "");
+	}
+
+	#[test]
+	fn price_settings_live_in_their_own_section() {
+		let config: PotConfig = toml::from_str("currency = \"EUR\"\n\n[prices]\nprovider = \"manual\"\n\n[prices.quotes]\nVTI = 200.0\n").unwrap();
+		assert_eq!(config.prices.provider(), PriceProvider::Manual);
+		assert_eq!(config.prices.quotes[&AssetTag::from("VTI")], 200.0);
+		assert_eq!(toml::from_str::<PotConfig>(&toml::to_string(&config).unwrap()).unwrap(), config);
+	}
+
+	#[test]
+	fn band_without_limits_uses_the_default_relative_band() {
+		let band = Band::default();
+		assert!(band.contains(0.0, 1000.0));
+		assert!(band.contains(-50.0, 1000.0));
+		assert!(!band.contains(50.01, 1000.0));
+		assert!(!band.contains(0.01, 0.0));
+	}
+
+	#[test]
+	fn band_holds_drift_within_every_limit() {
+		let absolute = Band { absolute: Some(50.0), relative: None };
+		assert!(absolute.contains(-50.0, 1000.0));
+		assert!(!absolute.contains(50.5, 1000.0));
+		let relative = Band { absolute: None, relative: Some(5.0) };
+		assert!(relative.contains(50.0, 1000.0));
+		assert!(!relative.contains(-60.0, 1000.0));
+		let both = Band { absolute: Some(100.0), relative: Some(5.0) };
+		assert!(both.contains(40.0, 1000.0));
+		assert!(!both.contains(60.0, 1000.0));
+		assert!(!both.contains(150.0, 10000.0));
+	}
+
+	#[test]
+	fn asset_bands_fall_back_to_the_global_band() {
+		let mut config = RebalanceConfig { absolute: Some(100.0), relative: Some(5.0), ..RebalanceConfig::default() };
+		config.bands.insert(AssetTag::from("VTI"), Band { absolute: None, relative: Some(1.0) });
+		assert_eq!(config.band(&AssetTag::from("VTI")), Band { absolute: Some(100.0), relative: Some(1.0) });
+		assert_eq!(config.band(&AssetTag::from("BND")), Band { absolute: Some(100.0), relative: Some(5.0) });
+	}
+
+	#[test]
+	fn registered_custodians_reject_unknown_ids() {
+		let mut config = PotConfig::default();
+		assert!(config.check_custodian("anything").is_ok());
+		let ira = CustodianConfig { name: Some("Rollover IRA".to_string()), account_type: AccountType::parse("ira"), ..CustodianConfig::default() };
+		config.custodians.insert("ira".to_string(), ira);
+		config.custodians.insert("brokerage".to_string(), CustodianConfig::default());
+		assert!(config.check_custodian("ira").is_ok());
+		assert!(config.check_custodian(":kid").is_ok());
+		assert!(matches!(config.check_custodian("iar"), Err(PotError::UnknownCustodian { .. })));
+		assert_eq!(config.account_type("ira"), Some(AccountType::TaxDeferred));
+		assert_eq!(config.account_type("brokerage"), Some(AccountType::Taxable));
+		assert_eq!(config.account_type("iar"), None);
+		assert_eq!(config.custodians["ira"].display_name("ira"), "Rollover IRA");
+		assert_eq!(config.custodians["brokerage"].display_name("brokerage"), "brokerage");
+	}
+}
@@ -1,21 +1,24 @@
-use std::collections::{BTreeSet, HashMap, HashSet};
-use std::error::Error;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
 
 pub use deep_asset::*;
 pub use pot_path::*;
 
 use crate::asset_tag::AssetTag;
+use crate::error::PotError;
 use crate::portfolio::Portfolio;
 use crate::pot::{FolderPot, Pot};
 
 mod pot_path;
 mod deep_asset;
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Ramp { Golden, Flat }
 
 impl Ramp {
-	pub fn from_str(s: &str) -> Self {
+	pub fn parse(s: &str) -> Self {
 		let ramp = match s.to_lowercase().trim() {
 			"golden" => Ramp::Golden,
 			"flat" => Ramp::Flat,
@@ -59,14 +62,13 @@ impl AssetGroup {
 		assets.insert(asset.clone());
 		AssetGroup { tag: self.tag.clone(), assets }
 	}
-	pub fn market_value(&self, prices: &HashMap<AssetTag, f64>) -> Result<f64, Box<dyn Error>> {
+	pub fn market_value(&self, prices: &HashMap<AssetTag, f64>) -> Result<f64, PotError> {
 		let mut sum = 0.0;
 		for asset in &self.assets {
 			let pot = FolderPot::from_pot_path(&asset.pot_path);
-			let portfolio = Portfolio { lots: pot.read_lots()?, free_cash: 0.0 };
-			let values = portfolio.market_values(prices);
-			let value = values.get(&asset.asset_tag).expect("value");
-			sum += *value;
+			let portfolio = Portfolio { lots: pot.read_lots()?, free_cash: BTreeMap::new() };
+			let values = portfolio.market_values(prices)?;
+			sum += values.get(&asset.asset_tag).cloned().unwrap_or(0.0);
 		}
 		Ok(sum)
 	}
@@ -84,5 +86,5 @@ pub fn into_groups(assets: HashSet<DeepAsset>) -> HashSet<AssetGroup> {
 		};
 		groups.insert(asset.asset_tag.clone(), group);
 	}
-	groups.values().into_iter().cloned().collect()
+	groups.values().cloned().collect()
 }
\ No newline at end of file
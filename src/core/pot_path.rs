@@ -1,3 +1,7 @@
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
 pub enum PotPath {
 	CurrentFolder,
@@ -5,7 +9,7 @@ pub enum PotPath {
 }
 
 impl PotPath {
-	pub fn from_str(s: &str) -> Self {
+	pub fn parse(s: &str) -> Self {
 		let s = s.trim();
 		match s {
 			"" | "." | "::" => PotPath::CurrentFolder,
@@ -33,6 +37,30 @@ impl PotPath {
 	}
 }
 
+impl fmt::Display for PotPath {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			PotPath::CurrentFolder => f.write_str("."),
+			PotPath::SubFolder(..) => {
+				let names = self.segment_names().into_iter().filter(|name| !name.is_empty()).collect::<Vec<_>>();
+				f.write_str(&names.join("::"))
+			}
+		}
+	}
+}
+
+impl<'de> Deserialize<'de> for PotPath {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+		String::deserialize(deserializer).map(|s| PotPath::parse(&s))
+	}
+}
+
+impl Serialize for PotPath {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+		serializer.serialize_str(&self.to_string())
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use crate::core::pot_path::PotPath;
@@ -42,4 +70,12 @@ mod tests {
 		let path = PotPath::CurrentFolder.extend("a").extend("b");
 		assert_eq!(path.segment_names(), vec!["".to_string(), "a".into(), "b".into()]);
 	}
+
+	#[test]
+	fn displays_and_parses_back() {
+		let path = PotPath::CurrentFolder.extend("a").extend("kid");
+		assert_eq!(path.to_string(), "a::kid");
+		assert_eq!(PotPath::parse(&path.to_string()), path);
+		assert_eq!(PotPath::parse(&PotPath::CurrentFolder.to_string()), PotPath::CurrentFolder);
+	}
 }
@@ -0,0 +1,467 @@
+use std::collections::HashMap;
+use std::io::{self, Stdout};
+use std::panic;
+use std::path::Path;
+
+use crossterm::ErrorKind;
+use crossterm::cursor::Show;
+use crossterm::event::{self, Event, KeyCode};
+use crossterm::execute;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use tui::backend::CrosstermBackend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState};
+use tui::{Frame, Terminal};
+
+use pot::{AssetTag, Ladder, Lot, Pot, PotError, PotPath, ShareCount, StoredPot};
+use pot::config::PotConfig;
+use pot::prices::{self, fetch_prices};
+use pot::report::{self, Status};
+
+use crate::cli::{dollars, dollars_delta, move_target};
+
+type Backend = CrosstermBackend<Stdout>;
+
+const STATUS_WIDTHS: [Constraint; 6] = [
+	Constraint::Length(10), Constraint::Length(10), Constraint::Length(10),
+	Constraint::Length(7), Constraint::Length(7), Constraint::Length(10),
+];
+const LOT_WIDTHS: [Constraint; 5] = [
+	Constraint::Length(16), Constraint::Length(10), Constraint::Length(10),
+	Constraint::Length(12), Constraint::Length(12),
+];
+const TARGET_WIDTHS: [Constraint; 2] = [Constraint::Length(12), Constraint::Length(7)];
+const TREE_WIDTHS: [Constraint; 2] = [Constraint::Length(16), Constraint::Length(10)];
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+enum Panel { Status, Lots, Targets, Tree }
+
+impl Panel {
+	fn next(&self) -> Panel {
+		match self {
+			Panel::Status => Panel::Lots,
+			Panel::Lots => Panel::Targets,
+			Panel::Targets => Panel::Tree,
+			Panel::Tree => Panel::Status,
+		}
+	}
+	fn previous(&self) -> Panel {
+		match self {
+			Panel::Status => Panel::Tree,
+			Panel::Lots => Panel::Status,
+			Panel::Targets => Panel::Lots,
+			Panel::Tree => Panel::Targets,
+		}
+	}
+}
+
+struct Dashboard {
+	root: StoredPot,
+	pot: StoredPot,
+	config: PotConfig,
+	prices: HashMap<AssetTag, f64>,
+	values: HashMap<PotPath, f64>,
+	status: Status,
+	lots: Vec<Lot>,
+	targets: Vec<(AssetTag, Option<AssetTag>)>,
+	portions: HashMap<AssetTag, f64>,
+	tree: Vec<(usize, AssetTag, StoredPot)>,
+	focus: Panel,
+	selections: HashMap<Panel, TableState>,
+	editing: Option<String>,
+	message: String,
+}
+
+pub fn run() -> Result<(), PotError> {
+	let root = StoredPot::open()?;
+	let prices = fetch_prices(&root)?;
+	let mut dashboard = Dashboard::new(root, prices)?;
+	let _guard = TerminalGuard::enter()?;
+	let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))
+		.map_err(|err| PotError::io(Path::new("terminal"), err))?;
+	dashboard.run(&mut terminal)
+}
+
+// Restores the terminal however the dashboard ends. The panic hook restores it
+// before the panic message prints so the message lands on the normal screen.
+struct TerminalGuard;
+
+impl TerminalGuard {
+	fn enter() -> Result<Self, PotError> {
+		enable_raw_mode().map_err(terminal_error)?;
+		let guard = TerminalGuard;
+		execute!(io::stdout(), EnterAlternateScreen).map_err(terminal_error)?;
+		let hook = panic::take_hook();
+		panic::set_hook(Box::new(move |info| {
+			restore_terminal();
+			hook(info);
+		}));
+		Ok(guard)
+	}
+}
+
+impl Drop for TerminalGuard {
+	fn drop(&mut self) {
+		restore_terminal();
+		if !std::thread::panicking() {
+			let _ = panic::take_hook();
+		}
+	}
+}
+
+fn restore_terminal() {
+	let _ = disable_raw_mode();
+	let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+}
+
+fn terminal_error(err: ErrorKind) -> PotError {
+	let source = match err {
+		ErrorKind::IoError(source) => source,
+		err => io::Error::other(err.to_string()),
+	};
+	PotError::io(Path::new("terminal"), source)
+}
+
+impl Dashboard {
+	fn new(root: StoredPot, prices: HashMap<AssetTag, f64>) -> Result<Self, PotError> {
+		let mut dashboard = Dashboard {
+			pot: root.clone(),
+			config: root.read_config()?,
+			status: report::status(&root, &prices)?,
+			root,
+			prices,
+			values: HashMap::new(),
+			lots: Vec::new(),
+			targets: Vec::new(),
+			portions: HashMap::new(),
+			tree: Vec::new(),
+			focus: Panel::Status,
+			selections: HashMap::new(),
+			editing: None,
+			message: String::new(),
+		};
+		dashboard.refresh()?;
+		Ok(dashboard)
+	}
+
+	// Re-reads the current pot and recomputes sub-pot values and drift from the
+	// prices fetched at startup.
+	fn refresh(&mut self) -> Result<(), PotError> {
+		self.values = prices::pot_values(&self.root, &self.prices)?;
+		self.config = self.pot.read_config()?;
+		self.status = report::status(&self.pot, &prices::subpot_prices(&self.pot, &self.prices, &self.values)?)?;
+		self.lots = self.pot.read_lots()?;
+		let ladder = self.pot.read_ladder()?;
+		self.targets = ladder.rows_descending();
+		self.portions = ladder.target_portions();
+		self.portions.extend(ladder.group_portions());
+		self.tree = vec![(0, AssetTag::from(":"), self.root.clone())];
+		add_branches(&self.root, 1, &mut self.tree)?;
+		let lengths = [
+			(Panel::Status, self.status.rows.len()),
+			(Panel::Lots, self.lots.len()),
+			(Panel::Targets, self.targets.len()),
+			(Panel::Tree, self.tree.len()),
+		];
+		for (panel, length) in lengths.iter() {
+			let state = self.selections.entry(*panel).or_default();
+			let selected = match state.selected() {
+				_ if *length == 0 => None,
+				Some(index) => Some(index.min(length - 1)),
+				None => Some(0),
+			};
+			state.select(selected);
+		}
+		Ok(())
+	}
+
+	fn selected(&self, panel: Panel) -> Option<usize> {
+		self.selections.get(&panel).and_then(TableState::selected)
+	}
+
+	fn run(&mut self, terminal: &mut Terminal<Backend>) -> Result<(), PotError> {
+		loop {
+			terminal.draw(|frame| self.draw(frame)).map_err(|err| PotError::io(Path::new("terminal"), err))?;
+			if let Event::Key(key) = event::read().map_err(terminal_error)? {
+				if self.editing.is_some() {
+					self.edit(key.code);
+					continue;
+				}
+				self.message.clear();
+				let result = match key.code {
+					KeyCode::Char('q') => return Ok(()),
+					KeyCode::Tab => {
+						self.focus = self.focus.next();
+						Ok(())
+					}
+					KeyCode::BackTab => {
+						self.focus = self.focus.previous();
+						Ok(())
+					}
+					KeyCode::Down | KeyCode::Char('j') => {
+						self.step(1);
+						Ok(())
+					}
+					KeyCode::Up | KeyCode::Char('k') => {
+						self.step(-1);
+						Ok(())
+					}
+					KeyCode::Char('+') | KeyCode::Char('p') => self.move_target(Ladder::promote_target, "Promoted"),
+					KeyCode::Char('-') | KeyCode::Char('d') => self.move_target(Ladder::demote_target, "Demoted"),
+					KeyCode::Char('e') => {
+						self.start_edit();
+						Ok(())
+					}
+					KeyCode::Enter => self.drill(),
+					KeyCode::Backspace | KeyCode::Esc => self.climb(),
+					_ => Ok(()),
+				};
+				if let Err(err) = result {
+					self.message = err.to_string();
+				}
+			}
+		}
+	}
+
+	fn step(&mut self, delta: isize) {
+		let length = match self.focus {
+			Panel::Status => self.status.rows.len(),
+			Panel::Lots => self.lots.len(),
+			Panel::Targets => self.targets.len(),
+			Panel::Tree => self.tree.len(),
+		};
+		if length > 0 {
+			let state = self.selections.entry(self.focus).or_default();
+			let index = state.selected().unwrap_or(0) as isize + delta;
+			state.select(Some(index.rem_euclid(length as isize) as usize));
+		}
+	}
+
+	fn move_target(&mut self, step: fn(&mut Ladder, &AssetTag) -> Option<usize>, verb: &str) -> Result<(), PotError> {
+		let asset = match (self.focus, self.selected(self.focus)) {
+			(Panel::Targets, Some(index)) => self.targets[index].0.clone(),
+			(Panel::Status, Some(index)) => self.status.rows[index].asset.clone(),
+			_ => return Ok(()),
+		};
+		match move_target(&self.pot, &asset, step)? {
+			None => self.message = format!("{} is not a pot target", asset.as_str()),
+			Some(position) => self.message = format!("{} {} to position {}", verb, asset.as_str(), position),
+		}
+		self.refresh()?;
+		if self.focus == Panel::Targets {
+			let index = self.targets.iter().position(|(target, _)| target == &asset);
+			self.selections.entry(Panel::Targets).or_default().select(index);
+		}
+		Ok(())
+	}
+
+	fn start_edit(&mut self) {
+		match self.selected(Panel::Lots).map(|index| &self.lots[index]) {
+			Some(lot) if self.focus == Panel::Lots && lot.asset_tag.is_subpot() => {
+				self.message = format!("{} holds its value in the sub-pot, drill in to edit it", lot.asset_tag.as_str());
+			}
+			Some(lot) if self.focus == Panel::Lots => self.editing = Some(lot.share_count.as_f64().to_string()),
+			_ => self.message = "Select a lot to edit its share count".to_string(),
+		}
+	}
+
+	fn edit(&mut self, code: KeyCode) {
+		let input = self.editing.get_or_insert_with(String::new);
+		match code {
+			KeyCode::Char(c) if c.is_ascii_digit() || c == '.' => input.push(c),
+			KeyCode::Backspace => { input.pop(); }
+			KeyCode::Esc => self.editing = None,
+			KeyCode::Enter => {
+				let input = self.editing.take().unwrap_or_default();
+				if let Err(err) = self.write_shares(&input) {
+					self.message = err.to_string();
+				}
+			}
+			_ => {}
+		}
+	}
+
+	fn write_shares(&mut self, input: &str) -> Result<(), PotError> {
+		let count = input.trim().parse::<f64>()
+			.ok()
+			.filter(|count| count.is_finite() && *count >= 0.0)
+			.ok_or_else(|| PotError::invalid_argument("share count", input))?;
+		let uid = match self.selected(Panel::Lots) {
+			Some(index) => self.lots[index].uid,
+			None => return Ok(()),
+		};
+		let mut lots = self.pot.read_lots()?;
+		if let Some(lot) = lots.iter_mut().find(|lot| lot.uid == uid) {
+			lot.share_count = ShareCount(lot.asset_tag.round_shares(count));
+			self.message = format!("Set {} at {} to {} shares", lot.asset_tag.as_str(), lot.custodian.as_str(), lot.share_count.as_f64());
+		}
+		self.pot.write_lots(&lots)?;
+		self.refresh()
+	}
+
+	fn drill(&mut self) -> Result<(), PotError> {
+		let subpot = match (self.focus, self.selected(self.focus)) {
+			(Panel::Tree, Some(index)) => self.tree[index].2.clone(),
+			(Panel::Status, Some(index)) if self.status.rows[index].asset.is_subpot() => {
+				self.pot.subpot(self.status.rows[index].asset.as_folder_name()?).as_ref().clone()
+			}
+			_ => return Ok(()),
+		};
+		self.enter(subpot)
+	}
+
+	fn climb(&mut self) -> Result<(), PotError> {
+		match self.pot.parent() {
+			Some(parent) => self.enter(parent),
+			None => Ok(()),
+		}
+	}
+
+	fn enter(&mut self, pot: StoredPot) -> Result<(), PotError> {
+		let previous = std::mem::replace(&mut self.pot, pot);
+		for panel in [Panel::Status, Panel::Lots, Panel::Targets].iter() {
+			self.selections.remove(panel);
+		}
+		if let Err(err) = self.refresh() {
+			self.pot = previous;
+			self.refresh()?;
+			return Err(err);
+		}
+		Ok(())
+	}
+
+	fn draw(&mut self, frame: &mut Frame<Backend>) {
+		let rows = Layout::default()
+			.direction(Direction::Vertical)
+			.constraints([Constraint::Percentage(60), Constraint::Min(5), Constraint::Length(1)].as_ref())
+			.split(frame.size());
+		let top = Layout::default()
+			.direction(Direction::Horizontal)
+			.constraints([Constraint::Percentage(55), Constraint::Percentage(20), Constraint::Percentage(25)].as_ref())
+			.split(rows[0]);
+		self.draw_status(frame, top[0]);
+		self.draw_targets(frame, top[1]);
+		self.draw_tree(frame, top[2]);
+		self.draw_lots(frame, rows[1]);
+		let footer = match &self.editing {
+			Some(input) => Spans::from(vec![
+				Span::styled("Shares: ", Style::default().add_modifier(Modifier::BOLD)),
+				Span::raw(format!("{}_  (enter saves, esc cancels)", input)),
+			]),
+			None if !self.message.is_empty() => Spans::from(Span::raw(self.message.clone())),
+			None => Spans::from(Span::styled(
+				"tab panel  ↑↓ select  p/d promote/demote  e edit shares  enter drill in  esc up  q quit",
+				Style::default().fg(Color::DarkGray),
+			)),
+		};
+		frame.render_widget(Paragraph::new(footer), rows[2]);
+	}
+
+	fn block(&self, panel: Panel, title: String) -> Block<'static> {
+		let style = if self.focus == panel { Style::default().fg(Color::Yellow) } else { Style::default() };
+		Block::default().borders(Borders::ALL).border_style(style).title(title)
+	}
+
+	fn render_table(&mut self, frame: &mut Frame<Backend>, area: Rect, panel: Panel, table: Table) {
+		let table = table.highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+		let state = self.selections.entry(panel).or_default();
+		frame.render_stateful_widget(table, area, state);
+	}
+
+	fn draw_status(&mut self, frame: &mut Frame<Backend>, area: Rect) {
+		let config = &self.config;
+		let decimals = config.display.percent_decimals();
+		let rows = self.status.rows.iter()
+			.map(|row| {
+				let name = if row.group.is_some() { format!(" {}", row.asset.as_str()) } else { row.asset.to_string() };
+				let shares = if row.asset.is_group() { String::new() } else { format!("{:.2}", row.shares) };
+				let action = if row.in_band { "ok".to_string() } else { dollars_delta(config, row.action()) };
+				let style = if row.in_band { Style::default() } else { Style::default().fg(Color::Red) };
+				Row::new(vec![
+					Cell::from(name),
+					Cell::from(shares),
+					Cell::from(dollars(config, row.market_value)),
+					Cell::from(format!("{:.*}%", decimals, row.market_portion * 100.0)),
+					Cell::from(format!("{:.*}%", decimals, row.target_portion * 100.0)),
+					Cell::from(action).style(style),
+				])
+			})
+			.collect::<Vec<_>>();
+		let title = format!("Status {}  free cash {}", self.pot.pot_path(), dollars(config, self.status.free_cash));
+		let table = Table::new(rows)
+			.header(header(&["ASSET", "SHARES", "MARKET", "%PF", "TARGET", "ACTION"]))
+			.block(self.block(Panel::Status, title))
+			.widths(&STATUS_WIDTHS);
+		self.render_table(frame, area, Panel::Status, table);
+	}
+
+	fn draw_lots(&mut self, frame: &mut Frame<Backend>, area: Rect) {
+		let rows = self.lots.iter()
+			.map(|lot| Row::new(vec![
+				Cell::from(format!("{:016x}", lot.uid)),
+				Cell::from(lot.custodian.as_str().to_string()),
+				Cell::from(lot.asset_tag.as_str().to_string()),
+				Cell::from(lot.share_count.as_f64().to_string()),
+				Cell::from(lot.basis.map(|basis| format!("{:.2}", basis)).unwrap_or_default()),
+			]))
+			.collect::<Vec<_>>();
+		let table = Table::new(rows)
+			.header(header(&["LOT ID", "CUSTODY", "SYMBOL", "COUNT", "BASIS"]))
+			.block(self.block(Panel::Lots, "Lots".to_string()))
+			.widths(&LOT_WIDTHS);
+		self.render_table(frame, area, Panel::Lots, table);
+	}
+
+	fn draw_targets(&mut self, frame: &mut Frame<Backend>, area: Rect) {
+		let rows = self.targets.iter()
+			.map(|(asset, group)| {
+				let name = if group.is_some() { format!(" {}", asset.as_str()) } else { asset.to_string() };
+				let portion = self.portions.get(asset).cloned().unwrap_or(0.0);
+				Row::new(vec![Cell::from(name), Cell::from(format!("{:.1}%", portion * 100.0))])
+			})
+			.collect::<Vec<_>>();
+		let table = Table::new(rows)
+			.header(header(&["TARGET", "%"]))
+			.block(self.block(Panel::Targets, "Targets".to_string()))
+			.widths(&TARGET_WIDTHS);
+		self.render_table(frame, area, Panel::Targets, table);
+	}
+
+	fn draw_tree(&mut self, frame: &mut Frame<Backend>, area: Rect) {
+		let current = self.pot.pot_path();
+		let rows = self.tree.iter()
+			.map(|(depth, tag, pot)| {
+				let name = if *depth == 0 { ".".to_string() } else { format!("{}{}", "  ".repeat(*depth), tag.as_str()) };
+				let value = self.values.get(pot.pot_path()).map(|value| dollars(&self.config, *value)).unwrap_or_default();
+				let style = if pot.pot_path() == current {
+					Style::default().add_modifier(Modifier::BOLD)
+				} else {
+					Style::default()
+				};
+				Row::new(vec![Cell::from(name), Cell::from(value)]).style(style)
+			})
+			.collect::<Vec<_>>();
+		let table = Table::new(rows)
+			.header(header(&["POT", "VALUE"]))
+			.block(self.block(Panel::Tree, "Pots".to_string()))
+			.widths(&TREE_WIDTHS);
+		self.render_table(frame, area, Panel::Tree, table);
+	}
+}
+
+fn header(titles: &[&'static str]) -> Row<'static> {
+	Row::new(titles.iter().map(|title| Cell::from(*title))).style(Style::default().add_modifier(Modifier::BOLD))
+}
+
+fn add_branches(pot: &StoredPot, depth: usize, tree: &mut Vec<(usize, AssetTag, StoredPot)>) -> Result<(), PotError> {
+	let mut subpots = pot.read_lot_assets()?.into_iter().filter(AssetTag::is_subpot).collect::<Vec<_>>();
+	subpots.sort();
+	for tag in subpots {
+		let subpot = pot.subpot(tag.as_folder_name()?).as_ref().clone();
+		tree.push((depth, tag, subpot.clone()));
+		add_branches(&subpot, depth + 1, tree)?;
+	}
+	Ok(())
+}
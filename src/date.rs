@@ -0,0 +1,50 @@
+use chrono::{Datelike, Local, NaiveDate};
+
+use crate::error::PotError;
+
+pub fn today() -> NaiveDate {
+	Local::now().naive_local().date()
+}
+
+pub fn parse_date(s: &str) -> Option<NaiveDate> {
+	NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok()
+}
+
+pub fn parse_date_arg(name: &str, s: &str) -> Result<NaiveDate, PotError> {
+	parse_date(s).ok_or_else(|| PotError::invalid_argument(name, s))
+}
+
+// Held more than one calendar year: sold after the anniversary of the acquisition,
+// where a Feb 29 acquisition has its anniversary on Feb 28.
+pub fn is_long_term(acquired: NaiveDate, sold: NaiveDate) -> bool {
+	let year = acquired.year() + 1;
+	let anniversary = acquired.with_year(year)
+		.or_else(|| NaiveDate::from_ymd_opt(year, 2, 28))
+		.unwrap_or(NaiveDate::MAX);
+	sold > anniversary
+}
+
+pub fn months_between(from: NaiveDate, to: NaiveDate) -> i64 {
+	let months = (to.year() - from.year()) as i64 * 12 + to.month() as i64 - from.month() as i64;
+	if to.day() < from.day() { months - 1 } else { months }
+}
+
+#[cfg(test)]
+mod tests {
+	use chrono::NaiveDate;
+
+	use crate::date::is_long_term;
+
+	fn date(year: i32, month: u32, day: u32) -> NaiveDate { NaiveDate::from_ymd_opt(year, month, day).unwrap() }
+
+	#[test]
+	fn long_term_means_more_than_one_calendar_year() {
+		assert!(!is_long_term(date(2021, 1, 1), date(2022, 1, 1)));
+		assert!(is_long_term(date(2021, 1, 1), date(2022, 1, 2)));
+		assert!(!is_long_term(date(2020, 1, 1), date(2021, 1, 1)));
+		assert!(is_long_term(date(2020, 1, 1), date(2021, 1, 2)));
+		assert!(!is_long_term(date(2019, 3, 1), date(2020, 3, 1)));
+		assert!(!is_long_term(date(2020, 2, 29), date(2021, 2, 28)));
+		assert!(is_long_term(date(2020, 2, 29), date(2021, 3, 1)));
+	}
+}
@@ -1,40 +1,41 @@
-use std::error::Error;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::Path;
 
-use crate::asset_tag::AssetTag;
-use crate::ladder::Ladder;
-use crate::portfolio::Portfolio;
-use crate::pot::{FolderPot, Pot};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
-pub fn read_portfolio() -> Result<Portfolio, Box<dyn Error>> {
-	let pot = FolderPot::new();
-	let portfolio = Portfolio {
-		lots: pot.read_lots()?,
-		free_cash: pot.read_cash()?,
-	};
-	Ok(portfolio)
+use crate::error::PotError;
+
+pub fn read_f64(path: &Path) -> Result<f64, PotError> {
+	let s = read_string(path)?;
+	let value = s.trim().parse::<f64>().map_err(|err| PotError::parse(path, Some(1), err))?;
+	Ok(value)
 }
 
-pub fn read_ladder(pot: &FolderPot) -> Result<Ladder, Box<dyn Error>> {
-	let targets = pot.read_targets()?.into_iter().map(AssetTag::from).collect::<Vec<_>>();
-	let ramp = pot.read_ramp()?;
-	Ok(Ladder { targets, ramp })
+pub fn read_string(path: &Path) -> Result<String, PotError> {
+	let mut s = String::new();
+	File::open(path)
+		.and_then(|mut file| file.read_to_string(&mut s))
+		.map_err(|err| PotError::io(path, err))?;
+	Ok(s)
 }
 
-pub fn read_f64(path: &Path) -> Result<f64, Box<dyn Error>> {
-	let cash = read_string(path)?.parse::<f64>()?;
-	Ok(cash)
+pub fn write_string(path: &Path, string: &str) -> Result<(), PotError> {
+	File::create(path)
+		.and_then(|mut file| file.write_all(string.as_bytes()))
+		.map_err(|err| PotError::io(path, err))
 }
 
-pub fn read_string(path: &Path) -> Result<String, Box<dyn Error>> {
-	let mut s = String::new();
-	File::open(path)?.read_to_string(&mut s)?;
-	Ok(s)
+pub fn read_toml<T: DeserializeOwned>(path: &Path) -> Result<T, PotError> {
+	let s = read_string(path)?;
+	toml::from_str(&s).map_err(|err| {
+		let line = err.line_col().map(|(line, _)| line as u64 + 1);
+		PotError::parse(path, line, err)
+	})
 }
 
-pub fn write_string(path: &Path, string: &str) -> Result<(), Box<dyn Error>> {
-	File::create(path)?.write_all(string.as_bytes())?;
-	Ok(())
+pub fn write_toml<T: Serialize>(path: &Path, value: &T) -> Result<(), PotError> {
+	let s = toml::to_string(value).map_err(|err| PotError::parse(path, None, err))?;
+	write_string(path, &s)
 }
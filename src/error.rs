@@ -0,0 +1,144 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum PotError {
+	MissingPot { path: PathBuf },
+	Parse { path: PathBuf, line: Option<u64>, message: String },
+	MissingLot { custodian: String, symbol: String },
+	UnknownCustodian { custodian: String },
+	InsufficientShares { custodian: String, symbol: String, held: f64 },
+	NotAPot { tag: String },
+	Pricing { symbol: String, message: String },
+	UnsupportedFormat { path: PathBuf, version: u32, supported: u32 },
+	InvalidArgument { name: String, value: String },
+	Io { path: PathBuf, source: io::Error },
+	Database { path: PathBuf, source: rusqlite::Error },
+	OutOfBand { count: usize },
+}
+
+impl PotError {
+	pub fn parse(path: &Path, line: Option<u64>, message: impl ToString) -> Self {
+		PotError::Parse { path: path.to_owned(), line, message: message.to_string() }
+	}
+	pub fn io(path: &Path, source: io::Error) -> Self {
+		PotError::Io { path: path.to_owned(), source }
+	}
+	pub fn csv(path: &Path, err: csv::Error) -> Self {
+		let line = err.position().map(|position| position.line());
+		let message = err.to_string();
+		match err.into_kind() {
+			csv::ErrorKind::Io(source) => PotError::io(path, source),
+			_ => PotError::parse(path, line, message),
+		}
+	}
+	pub fn pricing(symbol: &str, message: impl ToString) -> Self {
+		PotError::Pricing { symbol: symbol.to_string(), message: message.to_string() }
+	}
+	pub fn invalid_argument(name: &str, value: &str) -> Self {
+		PotError::InvalidArgument { name: name.to_string(), value: value.to_string() }
+	}
+
+	pub fn exit_code(&self) -> i32 {
+		match self {
+			PotError::InvalidArgument { .. } => 2,
+			PotError::MissingPot { .. } => 3,
+			PotError::Parse { .. } => 4,
+			PotError::MissingLot { .. } => 5,
+			PotError::NotAPot { .. } => 6,
+			PotError::Pricing { .. } => 7,
+			PotError::Io { .. } => 8,
+			PotError::Database { .. } => 9,
+			PotError::UnsupportedFormat { .. } => 10,
+			PotError::UnknownCustodian { .. } => 11,
+			PotError::OutOfBand { .. } => 12,
+			PotError::InsufficientShares { .. } => 13,
+		}
+	}
+}
+
+impl fmt::Display for PotError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			PotError::MissingPot { path } => {
+				write!(f, "no pot found in {}, run `pot init` to create one", path.display())
+			}
+			PotError::Parse { path, line: Some(line), message } => {
+				write!(f, "{}:{}: {}", path.display(), line, message)
+			}
+			PotError::Parse { path, line: None, message } => {
+				write!(f, "{}: {}", path.display(), message)
+			}
+			PotError::MissingLot { custodian, symbol } => {
+				write!(f, "no {} lot held at {}", symbol, custodian)
+			}
+			PotError::UnknownCustodian { custodian } => {
+				write!(f, "{} is not a registered custodian, see `pot custodians`", custodian)
+			}
+			PotError::InsufficientShares { custodian, symbol, held } => {
+				write!(f, "only {} shares of {} held at {}", held, symbol, custodian)
+			}
+			PotError::NotAPot { tag } => write!(f, "{} is not a sub-pot", tag),
+			PotError::Pricing { symbol, message } => write!(f, "could not price {}: {}", symbol, message),
+			PotError::UnsupportedFormat { path, version, supported } => write!(
+				f, "pot in {} uses format {}, but this pot only understands up to format {}",
+				path.display(), version, supported
+			),
+			PotError::InvalidArgument { name, value } => write!(f, "invalid {}: {}", name, value),
+			PotError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+			PotError::Database { path, source } => write!(f, "{}: {}", path.display(), source),
+			PotError::OutOfBand { count: 1 } => write!(f, "1 asset is outside its rebalance band"),
+			PotError::OutOfBand { count } => write!(f, "{} assets are outside their rebalance bands", count),
+		}
+	}
+}
+
+impl Error for PotError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			PotError::Io { source, .. } => Some(source),
+			PotError::Database { source, .. } => Some(source),
+			_ => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashSet;
+	use std::io;
+	use std::path::PathBuf;
+
+	use crate::error::PotError;
+
+	#[test]
+	fn each_error_exits_with_its_own_code() {
+		let path = PathBuf::from("pot");
+		let errors = vec![
+			PotError::MissingPot { path: path.clone() },
+			PotError::parse(&path, Some(3), "bad row"),
+			PotError::MissingLot { custodian: "main".to_string(), symbol: "VTI".to_string() },
+			PotError::UnknownCustodian { custodian: "iar".to_string() },
+			PotError::InsufficientShares { custodian: "main".to_string(), symbol: "VTI".to_string(), held: 1.0 },
+			PotError::NotAPot { tag: "VTI".to_string() },
+			PotError::pricing("VTI", "no price available"),
+			PotError::UnsupportedFormat { path: path.clone(), version: 9, supported: 4 },
+			PotError::invalid_argument("ratio", "x"),
+			PotError::io(&path, io::Error::other("disk")),
+			PotError::Database { path, source: rusqlite::Error::QueryReturnedNoRows },
+			PotError::OutOfBand { count: 2 },
+		];
+		let codes = errors.iter().map(PotError::exit_code).collect::<HashSet<_>>();
+		assert_eq!(codes.len(), errors.len());
+		assert!(codes.iter().all(|code| *code >= 2));
+	}
+
+	#[test]
+	fn messages_name_the_problem() {
+		assert_eq!(PotError::parse(&PathBuf::from("lots.csv"), Some(3), "bad row").to_string(), "lots.csv:3: bad row");
+		assert_eq!(PotError::MissingLot { custodian: "main".to_string(), symbol: "VTI".to_string() }.to_string(), "no VTI lot held at main");
+		assert_eq!(PotError::OutOfBand { count: 1 }.to_string(), "1 asset is outside its rebalance band");
+	}
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::asset_tag::AssetTag;
+use crate::config::PotConfig;
+use crate::core::Ramp;
+use crate::disk;
+use crate::error::PotError;
+use crate::pot::{FolderPot, Pot};
+
+pub const FORMAT_VERSION: u32 = 4;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Manifest {
+	pub format: u32,
+	#[serde(flatten)]
+	pub config: PotConfig,
+}
+
+type Migration = fn(&FolderPot) -> Result<(), PotError>;
+
+// Each migration upgrades a pot from the format at its index to the next format.
+const MIGRATIONS: [Migration; FORMAT_VERSION as usize] = [
+	add_manifest,
+	move_ramp_into_manifest,
+	label_cash_currency,
+	add_lot_basis_columns,
+];
+
+fn add_manifest(_pot: &FolderPot) -> Result<(), PotError> {
+	Ok(())
+}
+
+fn move_ramp_into_manifest(pot: &FolderPot) -> Result<(), PotError> {
+	let ramp_file = pot.path().join("ramp.txt");
+	if ramp_file.exists() {
+		let ramp = Ramp::parse(&disk::read_string(&ramp_file)?);
+		let mut config = pot.read_local_config()?;
+		config.ramp = Some(ramp);
+		pot.write_local_config(&config)?;
+		std::fs::remove_file(&ramp_file).map_err(|err| PotError::io(&ramp_file, err))?;
+	}
+	Ok(())
+}
+
+fn label_cash_currency(pot: &FolderPot) -> Result<(), PotError> {
+	let cash_file = pot.path().join("cash.txt");
+	if cash_file.exists() {
+		let amount = disk::read_f64(&cash_file)?;
+		let mut balances = BTreeMap::new();
+		balances.insert(AssetTag::usd(), amount);
+		pot.write_cash_balances(&balances)?;
+	}
+	Ok(())
+}
+
+fn add_lot_basis_columns(pot: &FolderPot) -> Result<(), PotError> {
+	let mut pot = pot.clone();
+	let lots = pot.read_lots()?;
+	pot.write_lots(&lots)
+}
+
+pub fn check(pot: &FolderPot) -> Result<u32, PotError> {
+	let version = pot.read_format_version()?;
+	if version > FORMAT_VERSION {
+		Err(PotError::UnsupportedFormat { path: pot.path().to_owned(), version, supported: FORMAT_VERSION })
+	} else {
+		Ok(version)
+	}
+}
+
+pub fn needs_upgrade(pot: &FolderPot) -> Result<bool, PotError> {
+	for pot in with_deep_subpots(pot)? {
+		if check(&pot)? < FORMAT_VERSION {
+			return Ok(true);
+		}
+	}
+	Ok(false)
+}
+
+pub fn upgrade(pot: &FolderPot) -> Result<Vec<(FolderPot, u32)>, PotError> {
+	let mut upgraded = Vec::new();
+	for pot in with_deep_subpots(pot)? {
+		let version = check(&pot)?;
+		if version < FORMAT_VERSION {
+			for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+				migration(&pot)?;
+				pot.write_format_version(index as u32 + 1)?;
+			}
+			upgraded.push((pot, version));
+		}
+	}
+	Ok(upgraded)
+}
+
+fn with_deep_subpots(pot: &FolderPot) -> Result<Vec<FolderPot>, PotError> {
+	let mut pots = vec![pot.clone()];
+	pots.extend(pot.read_deep_subpots()?.into_iter().map(|(_, subpot)| *subpot));
+	Ok(pots)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::asset_tag::AssetTag;
+	use crate::core::Ramp;
+	use crate::format::{check, FORMAT_VERSION, upgrade};
+	use crate::pot::Pot;
+	use crate::testing::temp_pot;
+
+	#[test]
+	fn upgrades_legacy_pot() {
+		let (_dir, pot) = temp_pot("legacy");
+		std::fs::remove_file(pot.path().join("pot.toml")).unwrap();
+		std::fs::write(pot.path().join("ramp.txt"), "flat").unwrap();
+		std::fs::write(pot.path().join("cash.txt"), "12.5").unwrap();
+		std::fs::write(pot.path().join("lots.csv"), "custody,symbol,count,uid\nmain,VTI,3,7\n").unwrap();
+		assert_eq!(check(&pot).unwrap(), 0);
+		let upgraded = upgrade(&pot).unwrap();
+		assert_eq!(upgraded.len(), 1);
+		assert_eq!(check(&pot).unwrap(), FORMAT_VERSION);
+		assert_eq!(pot.read_ramp().unwrap(), Ramp::Flat);
+		assert!(!pot.path().join("ramp.txt").exists());
+		assert_eq!(pot.read_cash(&AssetTag::usd()).unwrap(), 12.5);
+		let lots = std::fs::read_to_string(pot.path().join("lots.csv")).unwrap();
+		assert!(lots.starts_with("custody,symbol,count,uid,basis,acquired\n"));
+		assert_eq!(pot.read_lots().unwrap()[0].uid, 7);
+	}
+
+	#[test]
+	fn refuses_newer_pot() {
+		let (_dir, pot) = temp_pot("newer");
+		pot.write_format_version(FORMAT_VERSION + 1).unwrap();
+		assert!(check(&pot).is_err());
+		assert!(upgrade(&pot).is_err());
+	}
+}
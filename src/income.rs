@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::Custodian;
+use crate::asset_tag::AssetTag;
+use crate::pot::Record;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Income {
+	pub date: NaiveDate,
+	#[serde(rename = "custody")]
+	pub custodian: Custodian,
+	#[serde(rename = "symbol")]
+	pub asset_tag: AssetTag,
+	pub amount: f64,
+	pub reinvested: Option<f64>,
+}
+
+impl Record for Income {
+	const NAME: &'static str = "income";
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum IncomeGrouping { Asset, Month, Year }
+
+impl IncomeGrouping {
+	pub fn parse(s: &str) -> Option<Self> {
+		match s.trim().to_lowercase().as_str() {
+			"asset" | "symbol" => Some(IncomeGrouping::Asset),
+			"month" => Some(IncomeGrouping::Month),
+			"year" => Some(IncomeGrouping::Year),
+			_ => None,
+		}
+	}
+	fn name(&self, income: &Income) -> String {
+		match self {
+			IncomeGrouping::Asset => income.asset_tag.as_str().to_string(),
+			IncomeGrouping::Month => format!("{}-{:02}", income.date.year(), income.date.month()),
+			IncomeGrouping::Year => income.date.year().to_string(),
+		}
+	}
+}
+
+pub fn income_by(records: &[Income], grouping: IncomeGrouping) -> Vec<(String, f64)> {
+	let mut totals: BTreeMap<String, f64> = BTreeMap::new();
+	for income in records {
+		*totals.entry(grouping.name(income)).or_insert(0.0) += income.amount;
+	}
+	totals.into_iter().collect()
+}
+
+pub fn trailing_income(records: &[Income], asset: &AssetTag, date: NaiveDate) -> f64 {
+	records.iter()
+		.filter(|income| &income.asset_tag == asset)
+		.filter(|income| income.date <= date && income.date > date - Duration::days(365))
+		.map(|income| income.amount)
+		.sum()
+}
+
+#[cfg(test)]
+mod tests {
+	use chrono::NaiveDate;
+
+	use crate::Custodian;
+	use crate::asset_tag::AssetTag;
+	use crate::income::{Income, income_by, IncomeGrouping, trailing_income};
+
+	#[test]
+	fn groups_and_trails_income() {
+		let income = |symbol, year, month, amount| Income {
+			date: NaiveDate::from_ymd_opt(year, month, 15).unwrap(),
+			custodian: Custodian("main".to_string()),
+			asset_tag: AssetTag::equity(symbol),
+			amount,
+			reinvested: None,
+		};
+		let records = [income("vti", 2024, 12, 5.0), income("vti", 2025, 3, 10.0), income("bnd", 2025, 3, 2.0)];
+		assert_eq!(income_by(&records, IncomeGrouping::Month), vec![("2024-12".to_string(), 5.0), ("2025-03".to_string(), 12.0)]);
+		assert_eq!(income_by(&records, IncomeGrouping::Year).len(), 2);
+		let date = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
+		assert_eq!(trailing_income(&records, &AssetTag::equity("vti"), date), 10.0);
+	}
+}
@@ -1,17 +1,38 @@
 use std::collections::{HashMap, HashSet};
 
 use crate::asset_tag::AssetTag;
+use crate::config::PotConfig;
 use crate::core::Ramp;
+use crate::error::PotError;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Ladder {
 	pub targets: Vec<AssetTag>,
 	pub ramp: Ramp,
+	pub groups: HashMap<AssetTag, Ladder>,
 }
 
 impl Ladder {
+	pub fn new(targets: Vec<AssetTag>, ramp: Ramp) -> Self {
+		Ladder { targets, ramp, groups: HashMap::new() }
+	}
+	pub fn with_groups(targets: Vec<AssetTag>, ramp: Ramp, config: &PotConfig) -> Result<Self, PotError> {
+		Ladder::build(targets, ramp, config, &mut Vec::new())
+	}
+	fn build(targets: Vec<AssetTag>, ramp: Ramp, config: &PotConfig, outer: &mut Vec<AssetTag>) -> Result<Self, PotError> {
+		let mut groups = HashMap::new();
+		for tag in targets.iter().filter(|it| it.is_group()) {
+			let group = config.group(tag).filter(|_| !outer.contains(tag))
+				.ok_or_else(|| PotError::invalid_argument("group", tag.as_str()))?;
+			outer.push(tag.clone());
+			let inner = Ladder::build(group.targets.clone(), group.ramp.unwrap_or(ramp), config, outer)?;
+			outer.pop();
+			groups.insert(tag.clone(), inner);
+		}
+		Ok(Ladder { targets, ramp, groups })
+	}
 	pub fn asset_portions(&self) -> Vec<(AssetTag, f64)> {
-		let portions = self.target_portions();
+		let portions = self.rung_portions();
 		self.targets.iter().map(|asset| {
 			let portion = match portions.get(asset) {
 				Some(portion) => portion.to_owned(),
@@ -24,13 +45,28 @@ impl Ladder {
 		self.targets.clone()
 	}
 	pub fn target_symbols_descending(&self) -> Vec<AssetTag> {
-		self.target_symbols_ascending()
-			.into_iter()
-			.rev()
-			.collect()
+		let mut symbols = Vec::new();
+		for (tag, _) in self.rows_descending() {
+			if !tag.is_group() && !symbols.contains(&tag) {
+				symbols.push(tag);
+			}
+		}
+		symbols
 	}
 	pub fn target_symbols(&self) -> HashSet<AssetTag> {
-		self.target_symbols_ascending().into_iter().collect()
+		self.target_symbols_descending().into_iter().collect()
+	}
+	pub fn rows_descending(&self) -> Vec<(AssetTag, Option<AssetTag>)> {
+		let mut rows = Vec::new();
+		for tag in self.targets.iter().rev() {
+			rows.push((tag.clone(), None));
+			if let Some(inner) = self.groups.get(tag) {
+				for (inner_tag, inner_group) in inner.rows_descending() {
+					rows.push((inner_tag, inner_group.or_else(|| Some(tag.clone()))));
+				}
+			}
+		}
+		rows
 	}
 	pub fn target_weights(&self) -> HashMap<AssetTag, f64> {
 		self.targets
@@ -39,13 +75,43 @@ impl Ladder {
 			.map(|(i, asset_type)| (asset_type.clone(), self.ramp.pow_weight(i)))
 			.collect::<HashMap<AssetTag, _>>()
 	}
-	pub fn target_portions(&self) -> HashMap<AssetTag, f64> {
+	pub fn rung_portions(&self) -> HashMap<AssetTag, f64> {
 		let weights = self.target_weights();
 		let full_weight: f64 = weights.values().sum();
 		weights.iter()
 			.map(|(asset_tag, weight)| (asset_tag.clone(), *weight / full_weight))
 			.collect::<HashMap<AssetTag, _>>()
 	}
+	pub fn target_portions(&self) -> HashMap<AssetTag, f64> {
+		let mut portions = HashMap::new();
+		for (tag, portion) in self.rung_portions() {
+			match self.groups.get(&tag) {
+				Some(inner) => for (inner_tag, inner_portion) in inner.target_portions() {
+					*portions.entry(inner_tag).or_insert(0.0) += portion * inner_portion;
+				},
+				None => *portions.entry(tag).or_insert(0.0) += portion,
+			}
+		}
+		portions
+	}
+	pub fn group_portions(&self) -> HashMap<AssetTag, f64> {
+		let mut portions = HashMap::new();
+		for (tag, portion) in self.rung_portions() {
+			if let Some(inner) = self.groups.get(&tag) {
+				for (inner_tag, inner_portion) in inner.group_portions() {
+					*portions.entry(inner_tag).or_insert(0.0) += portion * inner_portion;
+				}
+				*portions.entry(tag).or_insert(0.0) += portion;
+			}
+		}
+		portions
+	}
+	pub fn group_members(&self, group: &AssetTag) -> HashSet<AssetTag> {
+		match self.groups.get(group) {
+			Some(inner) => inner.target_symbols(),
+			None => self.groups.values().flat_map(|inner| inner.group_members(group)).collect(),
+		}
+	}
 	pub fn promote_target(&mut self, asset: &AssetTag) -> Option<usize> {
 		let position = self.targets.iter().position(|it| it == asset);
 		match position {
@@ -71,3 +137,33 @@ impl Ladder {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::asset_tag::AssetTag;
+	use crate::config::{GroupConfig, PotConfig};
+	use crate::core::Ramp;
+	use crate::ladder::Ladder;
+
+	#[test]
+	fn group_portions_are_hierarchical() {
+		let mut config = PotConfig::default();
+		let group = GroupConfig { ramp: Some(Ramp::Flat), targets: vec![AssetTag::equity("vti"), AssetTag::equity("vxus")] };
+		config.groups.insert("stocks".to_string(), group);
+		let targets = vec![AssetTag::equity("bnd"), AssetTag::group("stocks")];
+		let ladder = Ladder::with_groups(targets, Ramp::Flat, &config).unwrap();
+		let portions = ladder.target_portions();
+		assert_eq!(portions[&AssetTag::equity("bnd")], 0.5);
+		assert_eq!(portions[&AssetTag::equity("vti")], 0.25);
+		assert_eq!(portions[&AssetTag::equity("vxus")], 0.25);
+		assert_eq!(ladder.group_portions()[&AssetTag::group("stocks")], 0.5);
+		assert_eq!(ladder.target_symbols_descending(), vec![AssetTag::equity("vxus"), AssetTag::equity("vti"), AssetTag::equity("bnd")]);
+	}
+
+	#[test]
+	fn rejects_cyclic_groups() {
+		let mut config = PotConfig::default();
+		config.groups.insert("a".to_string(), GroupConfig { ramp: None, targets: vec![AssetTag::group("a")] });
+		assert!(Ladder::with_groups(vec![AssetTag::group("a")], Ramp::Golden, &config).is_err());
+	}
+}
@@ -0,0 +1,84 @@
+//! Reads, values and rebalances pots of assets.
+
+extern crate chrono;
+extern crate csv;
+extern crate hex;
+extern crate rand;
+extern crate rusqlite;
+extern crate serde;
+extern crate smarket;
+extern crate toml;
+
+use std::fmt;
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+pub use asset_tag::AssetTag;
+pub use config::PotConfig;
+pub use error::PotError;
+pub use core::{AssetGroup, DeepAsset, into_groups, PotPath, Ramp};
+pub use ladder::Ladder;
+pub use lot::Lot;
+pub use metadata::AssetMetadata;
+pub use portfolio::Portfolio;
+pub use sale::{ReliefMethod, Sale};
+pub use pot::{copy_pot, FolderPot, Pot, rename_custodian};
+pub use sqlite_pot::SqlitePot;
+pub use stored_pot::StoredPot;
+
+pub mod action;
+pub mod asset_tag;
+pub mod config;
+pub mod core;
+pub mod date;
+pub mod disk;
+pub mod error;
+pub mod format;
+pub mod income;
+pub mod ladder;
+pub mod lot;
+pub mod metadata;
+pub mod plan;
+pub mod portfolio;
+pub mod pot;
+pub mod prices;
+pub mod projection;
+pub mod sale;
+pub mod report;
+pub mod snapshot;
+pub mod sqlite_pot;
+pub mod stored_pot;
+#[cfg(test)]
+mod testing;
+pub mod wash;
+
+#[derive(Debug)]
+pub struct Holding {
+	pub symbol: String,
+	pub lots: Vec<Lot>,
+}
+
+#[derive(PartialOrd, PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct ShareCount(pub f64);
+
+impl ShareCount {
+	pub fn as_f64(&self) -> f64 { self.0 }
+	pub fn is_zero(&self) -> bool { self.0 == 0.0 }
+	pub fn is_non_zero(&self) -> bool { !self.is_zero() }
+}
+
+impl Display for ShareCount {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_fmt(format_args!("{}", self.0))
+	}
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Custodian(pub String);
+
+impl Custodian {
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
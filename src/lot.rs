@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
@@ -15,6 +16,10 @@ pub struct Lot {
 	pub share_count: ShareCount,
 	#[serde(default = "Lot::random_uid")]
 	pub uid: u64,
+	#[serde(default)]
+	pub basis: Option<f64>,
+	#[serde(default)]
+	pub acquired: Option<NaiveDate>,
 }
 
 impl Lot {
@@ -32,6 +37,21 @@ impl Lot {
 	pub fn with_share_count(&self, count: f64) -> Self {
 		Lot { share_count: ShareCount(count), ..self.clone() }
 	}
+	pub fn basis_per_share(&self) -> Option<f64> {
+		let count = self.share_count.as_f64();
+		self.basis.filter(|_| count > 0.0).map(|basis| basis / count)
+	}
+	pub fn split(&self, count: f64) -> (Lot, Lot) {
+		let portion = |basis: f64| basis * count / self.share_count.as_f64();
+		let taken = Lot { share_count: ShareCount(count), basis: self.basis.map(portion), ..self.clone() };
+		let rest = Lot {
+			share_count: ShareCount(self.share_count.as_f64() - count),
+			basis: self.basis.map(|basis| basis - portion(basis)),
+			uid: Lot::random_uid(),
+			..self.clone()
+		};
+		(taken, rest)
+	}
 	pub fn with_fresh_uid(&self) -> Self {
 		Lot { uid: Self::random_uid(), ..self.clone() }
 	}
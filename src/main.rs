@@ -1,52 +1,97 @@
 extern crate clap;
-extern crate csv;
-extern crate hex;
-extern crate rand;
-extern crate serde;
-extern crate smarket;
+extern crate pot;
+extern crate toml;
 
-use std::error::Error;
-use std::fmt;
-use std::fmt::Display;
+use std::path::Path;
 
-use serde::{Deserialize, Serialize};
+use chrono::Datelike;
 
-use lot::*;
+use pot::PotError;
 
-use crate::asset_tag::AssetTag;
-use crate::core::PotPath;
+use pot::{AssetTag, PotPath, ReliefMethod};
+use pot::action::{self, CorporateAction};
+use pot::config::{CustodianConfig, GoalConfig};
+use pot::date::{parse_date_arg, today};
+use pot::income::IncomeGrouping;
+use pot::plan::LotPreference;
+use pot::projection::Contribution;
 
-mod asset_tag;
 mod cli;
-mod core;
-mod disk;
-mod ladder;
-mod lot;
-mod portfolio;
-mod pot;
+mod dashboard;
 mod print;
 mod table;
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() {
+	if let Err(err) = run() {
+		eprintln!("pot: {}", err);
+		std::process::exit(err.exit_code());
+	}
+}
+
+fn run() -> Result<(), PotError> {
 	let yaml = clap::load_yaml!("cli.yaml");
 	let matches = clap::App::from(yaml).get_matches();
-	if let Some(_) = matches.subcommand_matches("init") {
+	if matches.subcommand_name() != Some("init") && matches.subcommand_name() != Some("upgrade") {
+		cli::upgrade_if_needed()?;
+	}
+	if matches.subcommand_matches("init").is_some() {
 		cli::init()?;
-	} else if let Some(_) = matches.subcommand_matches("status") {
+	} else if matches.subcommand_matches("status").is_some() {
 		cli::status()?;
+	} else if let Some(matches) = matches.subcommand_matches("deposit") {
+		let amount = parse_f64("AMOUNT", matches.value_of("AMOUNT").expect("amount"))?;
+		cli::deposit(amount, matches.is_present("plan"), matches.value_of("apply"))?;
+	} else if let Some(matches) = matches.subcommand_matches("withdraw") {
+		let amount = parse_f64("AMOUNT", matches.value_of("AMOUNT").expect("amount"))?;
+		let prefer = matches.value_of("prefer").unwrap_or("oldest");
+		let preference = LotPreference::parse(prefer).ok_or_else(|| PotError::invalid_argument("prefer", prefer))?;
+		cli::withdraw(amount, matches.is_present("plan"), matches.is_present("apply"), preference, matches.value_of("priority"))?;
+	} else if let Some(matches) = matches.subcommand_matches("project") {
+		let parse_count = |name: &str, default: usize| match matches.value_of(name) {
+			Some(it) => it.parse::<usize>().ok().filter(|it| *it > 0).ok_or_else(|| PotError::invalid_argument(name, it)),
+			None => Ok(default),
+		};
+		let years = parse_count("years", 30)?;
+		let paths = parse_count("paths", 1000)?;
+		let contrib = matches.value_of("contrib").unwrap_or("0");
+		let contribution = Contribution::parse(contrib).ok_or_else(|| PotError::invalid_argument("contrib", contrib))?;
+		let seed = matches.value_of("seed")
+			.map(|it| it.parse::<u64>().map_err(|_| PotError::invalid_argument("seed", it)))
+			.transpose()?;
+		cli::project(years, contribution, paths, seed)?;
+	} else if matches.subcommand_matches("goals").is_some() {
+		cli::goals()?;
+	} else if matches.subcommand_matches("tui").is_some() {
+		dashboard::run()?;
+	} else if let Some(matches) = matches.subcommand_matches("snapshot") {
+		let date = matches.value_of("date").map(|it| parse_date_arg("date", it)).transpose()?;
+		cli::snapshot(date.unwrap_or_else(today))?;
+	} else if matches.subcommand_matches("benchmark").is_some() {
+		cli::benchmark()?;
+	} else if let Some(matches) = matches.subcommand_matches("harvest") {
+		let threshold = matches.value_of("threshold").map(|it| parse_f64("threshold", it)).transpose()?;
+		cli::harvest(threshold.unwrap_or(0.0))?;
+	} else if matches.subcommand_matches("rebalance").is_some() {
+		cli::rebalance()?;
+	} else if matches.subcommand_matches("check").is_some() {
+		cli::check()?;
 	} else if let Some(matches) = matches.subcommand_matches("value") {
 		if matches.is_present("assets") {
 			cli::asset_values()?;
+		} else if let Some(key) = matches.value_of("by") {
+			cli::values_by(&key.to_lowercase())?;
 		} else {
 			let verbose = matches.is_present("verbose");
 			cli::value(verbose)?;
 		}
-	} else if let Some(_) = matches.subcommand_matches("lots") {
+	} else if matches.subcommand_matches("lots").is_some() {
 		cli::lots()?;
-	} else if let Some(_) = matches.subcommand_matches("assets") {
+	} else if matches.subcommand_matches("assets").is_some() {
 		cli::assets()?;
-	} else if let Some(_) = matches.subcommand_matches("cash") {
+	} else if matches.subcommand_matches("cash").is_some() {
 		cli::cash()?;
+	} else if let Some(matches) = matches.subcommand_matches("allocation") {
+		cli::allocation(matches.value_of("by").unwrap_or("class"))?;
 	} else if let Some(ramp_matches) = matches.subcommand_matches("ramp") {
 		if let Some(ramp_set_matches) = ramp_matches.subcommand_matches("set") {
 			let s = ramp_set_matches.value_of("RAMP").expect("ramp").to_lowercase();
@@ -58,6 +103,10 @@ fn main() -> Result<(), Box<dyn Error>> {
 		if let Some(matches) = matches.subcommand_matches("add") {
 			let symbols = matches.value_of("SYMBOLS").expect("symbols");
 			cli::add_targets(symbols)?;
+		} else if let Some(matches) = matches.subcommand_matches("group") {
+			let name = matches.value_of("NAME").expect("name");
+			let symbols = matches.value_of("SYMBOLS").expect("symbols");
+			cli::set_group(name, symbols, matches.value_of("ramp"))?;
 		} else {
 			cli::targets()?;
 		}
@@ -71,12 +120,34 @@ fn main() -> Result<(), Box<dyn Error>> {
 		// TODO Make this a subcommand of lots.
 		let custodian = matches.value_of("CUSTODIAN").expect("custodian");
 		let symbol = matches.value_of("SYMBOL").expect("symbol").to_uppercase();
-		let count = matches.value_of("COUNT").map(|s| s.parse::<f64>().expect("count"));
-		cli::shares(&custodian, &symbol, count)?;
+		let count = matches.value_of("COUNT").map(|s| parse_f64("COUNT", s)).transpose()?;
+		cli::shares(custodian, &symbol, count)?;
 	} else if let Some(matches) = matches.subcommand_matches("set") {
 		if let Some(matches) = matches.subcommand_matches("cash") {
-			let value = matches.value_of("VALUE").expect("value").parse::<f64>()?;
-			cli::set_cash(value)?;
+			let value = parse_f64("VALUE", matches.value_of("VALUE").expect("value"))?;
+			cli::set_cash(value, matches.value_of("CURRENCY"))?;
+		} else if let Some(matches) = matches.subcommand_matches("substitutes") {
+			let symbol = matches.value_of("SYMBOL").expect("symbol");
+			cli::set_substitutes(symbol, matches.value_of("SYMBOLS").expect("symbols"))?;
+		} else if let Some(matches) = matches.subcommand_matches("goal") {
+			let goal = match (matches.value_of("AMOUNT"), matches.value_of("DATE")) {
+				(Some(amount), Some(date)) => Some(GoalConfig {
+					amount: parse_f64("AMOUNT", amount)?,
+					date: parse_date_arg("DATE", date)?,
+					monthly: matches.value_of("monthly").map(|it| parse_f64("monthly", it)).transpose()?,
+				}),
+				_ => None,
+			};
+			cli::set_goal(goal)?;
+		} else if let Some(matches) = matches.subcommand_matches("benchmark") {
+			cli::set_benchmark(matches.value_of("WEIGHTS").unwrap_or(""))?;
+		} else if let Some(matches) = matches.subcommand_matches("yield") {
+			let percent = matches.value_of("PERCENT").map(|it| parse_f64("PERCENT", it)).transpose()?;
+			cli::set_yield(matches.value_of("SYMBOL").expect("symbol"), percent)?;
+		} else if let Some(matches) = matches.subcommand_matches("band") {
+			let absolute = matches.value_of("absolute").map(|it| parse_f64("absolute", it)).transpose()?;
+			let relative = matches.value_of("relative").map(|it| parse_f64("relative", it)).transpose()?;
+			cli::set_band(matches.value_of("SYMBOL"), absolute, relative)?;
 		} else {
 			println!("Set what?");
 		}
@@ -85,9 +156,13 @@ fn main() -> Result<(), Box<dyn Error>> {
 			let custody = matches.value_of("CUSTODY").expect("custody");
 			let symbol = matches.value_of("SYMBOL").expect("symbol");
 			let asset = AssetTag::from(symbol);
-			let share_count = matches.value_of("SHARECOUNT").expect("sharecount").parse::<f64>()?;
-			let uid = matches.value_of("UID").map_or(Ok(None), |it| it.parse::<u64>().map(Some))?;
-			cli::add_lot(custody, &asset, share_count, uid)?;
+			let share_count = parse_f64("SHARECOUNT", matches.value_of("SHARECOUNT").expect("sharecount"))?;
+			let uid = matches.value_of("UID")
+				.map(|it| it.parse::<u64>().map_err(|_| PotError::invalid_argument("UID", it)))
+				.transpose()?;
+			let basis = matches.value_of("basis").map(|it| parse_f64("basis", it)).transpose()?;
+			let acquired = matches.value_of("date").map(|it| parse_date_arg("date", it)).transpose()?;
+			cli::add_lot(custody, &asset, share_count, uid, basis, acquired)?;
 		} else if let Some(matches) = matches.subcommand_matches("target") {
 			let symbol = matches.value_of("SYMBOL").expect("symbol");
 			cli::add_targets(symbol)?;
@@ -104,42 +179,95 @@ fn main() -> Result<(), Box<dyn Error>> {
 		} else {
 			println!("Add what?");
 		}
+	} else if let Some(matches) = matches.subcommand_matches("sell") {
+		let custodian = matches.value_of("CUSTODY").expect("custody");
+		let symbol = matches.value_of("SYMBOL").expect("symbol");
+		let count = parse_f64("COUNT", matches.value_of("COUNT").expect("count"))?;
+		let method = matches.value_of("method").unwrap_or("fifo");
+		let method = ReliefMethod::parse(method).ok_or_else(|| PotError::invalid_argument("method", method))?;
+		let price = matches.value_of("price").map(|it| parse_f64("price", it)).transpose()?;
+		let date = matches.value_of("date").map(|it| parse_date_arg("date", it)).transpose()?;
+		cli::sell(custodian, symbol, count, method, price, date)?;
+	} else if let Some(matches) = matches.subcommand_matches("action") {
+		let date = matches.subcommand()
+			.and_then(|(_, matches)| matches.value_of("date"))
+			.map(|it| parse_date_arg("date", it))
+			.transpose()?
+			.unwrap_or_else(today);
+		if let Some(matches) = matches.subcommand_matches("split") {
+			let symbol = AssetTag::from(matches.value_of("SYMBOL").expect("symbol"));
+			let ratio = action::parse_ratio(matches.value_of("RATIO").expect("ratio"))?;
+			cli::corporate_action(CorporateAction::split(symbol, ratio, date))?;
+		} else if let Some(matches) = matches.subcommand_matches("rename") {
+			let old = AssetTag::from(matches.value_of("OLD").expect("old"));
+			let new = AssetTag::from(matches.value_of("NEW").expect("new"));
+			cli::corporate_action(CorporateAction::rename(old, new, date))?;
+		} else {
+			println!("Which action?");
+		}
+	} else if let Some(matches) = matches.subcommand_matches("income") {
+		if let Some(matches) = matches.subcommand_matches("add") {
+			let symbol = matches.value_of("SYMBOL").expect("symbol");
+			let amount = parse_f64("AMOUNT", matches.value_of("AMOUNT").expect("amount"))?;
+			let custodian = matches.value_of("custodian").expect("custodian");
+			let date = matches.value_of("date").map(|it| parse_date_arg("date", it)).transpose()?;
+			let price = matches.value_of("price").map(|it| parse_f64("price", it)).transpose()?;
+			cli::add_income(custodian, symbol, amount, date, matches.is_present("reinvest"), price)?;
+		} else if matches.is_present("projected") {
+			cli::projected_income()?;
+		} else {
+			let by = matches.value_of("by").unwrap_or("asset");
+			let grouping = IncomeGrouping::parse(by).ok_or_else(|| PotError::invalid_argument("by", by))?;
+			cli::income(grouping)?;
+		}
+	} else if matches.subcommand_matches("history").is_some() {
+		cli::history()?;
+	} else if let Some(matches) = matches.subcommand_matches("tax-report") {
+		let year = match matches.value_of("year") {
+			Some(year) => year.parse::<i32>().map_err(|_| PotError::invalid_argument("year", year))?,
+			None => today().year(),
+		};
+		let csv_path = matches.value_of("csv").map(Path::new);
+		cli::tax_report(year, matches.is_present("taxable"), csv_path)?;
 	} else if let Some(matches) = matches.subcommand_matches("gather") {
 		let symbol = matches.value_of("SYMBOL").expect("symbol");
-		let pot_path = matches.value_of("POT").map_or(PotPath::CurrentFolder, PotPath::from_str);
+		let pot_path = matches.value_of("POT").map_or(PotPath::CurrentFolder, PotPath::parse);
 		cli::gather_asset(symbol, &pot_path)?;
+	} else if matches.subcommand_matches("custodians").is_some() {
+		cli::custodians()?;
+	} else if matches.subcommand_matches("location").is_some() {
+		cli::location()?;
+	} else if let Some(matches) = matches.subcommand_matches("custodian") {
+		if let Some(matches) = matches.subcommand_matches("add") {
+			let id = matches.value_of("ID").expect("id");
+			let details = CustodianConfig {
+				name: matches.value_of("name").map(str::to_string),
+				institution: matches.value_of("institution").map(str::to_string),
+				suffix: matches.value_of("suffix").map(str::to_string),
+				account_type: matches.value_of("type").map(cli::parse_account_type).transpose()?,
+				owner: matches.value_of("owner").map(str::to_string),
+			};
+			cli::add_custodian(id, details)?;
+		} else if let Some(matches) = matches.subcommand_matches("rename") {
+			let old = matches.value_of("OLD").expect("old");
+			let new = matches.value_of("NEW").expect("new");
+			cli::rename_custodian(old, new)?;
+		} else {
+			println!("Custodian what?");
+		}
+	} else if matches.subcommand_matches("config").is_some() {
+		cli::config()?;
+	} else if matches.subcommand_matches("upgrade").is_some() {
+		cli::upgrade()?;
+	} else if let Some(matches) = matches.subcommand_matches("migrate") {
+		let format = matches.value_of("FORMAT").expect("format").to_lowercase();
+		cli::migrate(&format)?;
 	} else {
 		cli::status()?;
 	}
 	Ok(())
 }
 
-#[derive(Debug)]
-pub struct Holding {
-	pub symbol: String,
-	pub lots: Vec<Lot>,
-}
-
-#[derive(PartialOrd, PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
-pub struct ShareCount(f64);
-
-impl ShareCount {
-	pub fn as_f64(&self) -> f64 { self.0 }
-	pub fn is_zero(&self) -> bool { self.0 == 0.0 }
-	pub fn is_non_zero(&self) -> bool { !self.is_zero() }
-}
-
-impl Display for ShareCount {
-	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		f.write_fmt(format_args!("{}", self.0))
-	}
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Custodian(String);
-
-impl Custodian {
-	pub fn as_str(&self) -> &str {
-		&self.0
-	}
+fn parse_f64(name: &str, value: &str) -> Result<f64, PotError> {
+	value.parse::<f64>().map_err(|_| PotError::invalid_argument(name, value))
 }
@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::asset_tag::AssetTag;
+use crate::config::AccountType;
+
+pub const CLASS: &str = "class";
+pub const TAX_EFFICIENCY: &str = "tax-efficiency";
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum TaxEfficiency { Inefficient, Neutral, Efficient }
+
+impl TaxEfficiency {
+	pub fn of(asset: &AssetTag, metadata: &AssetMetadata) -> Self {
+		match metadata.category(asset, TAX_EFFICIENCY) {
+			Some("inefficient") => TaxEfficiency::Inefficient,
+			Some("efficient") => TaxEfficiency::Efficient,
+			Some(_) => TaxEfficiency::Neutral,
+			None => match asset {
+				AssetTag::Bond(_) | AssetTag::RealEstate(_) => TaxEfficiency::Inefficient,
+				AssetTag::Equity(_) => TaxEfficiency::Efficient,
+				_ => TaxEfficiency::Neutral,
+			},
+		}
+	}
+	pub fn preferred_accounts(&self) -> [AccountType; 4] {
+		match self {
+			TaxEfficiency::Inefficient => [AccountType::TaxDeferred, AccountType::Hsa, AccountType::TaxFree, AccountType::Taxable],
+			TaxEfficiency::Neutral => [AccountType::TaxFree, AccountType::Hsa, AccountType::TaxDeferred, AccountType::Taxable],
+			TaxEfficiency::Efficient => [AccountType::Taxable, AccountType::TaxFree, AccountType::TaxDeferred, AccountType::Hsa],
+		}
+	}
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AssetMetadata(pub BTreeMap<AssetTag, BTreeMap<String, String>>);
+
+impl AssetMetadata {
+	pub fn is_empty(&self) -> bool { self.0.is_empty() }
+	pub fn category(&self, asset: &AssetTag, key: &str) -> Option<&str> {
+		self.0.get(asset).and_then(|categories| categories.get(key)).map(String::as_str)
+	}
+	pub fn set_category(&mut self, asset: &AssetTag, key: &str, value: &str) {
+		let categories = self.0.entry(asset.clone()).or_default();
+		categories.insert(key.to_lowercase(), value.to_string());
+	}
+}
+
+pub fn default_category(asset: &AssetTag, key: &str) -> String {
+	if key == CLASS {
+		asset.class_name().to_string()
+	} else {
+		"unclassified".to_string()
+	}
+}
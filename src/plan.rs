@@ -0,0 +1,245 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use crate::asset_tag::AssetTag;
+use crate::date;
+use crate::error::PotError;
+use crate::lot::Lot;
+use crate::pot::Pot;
+use crate::prices::local_prices;
+
+#[derive(Clone, Debug)]
+pub struct Buy {
+	pub asset: AssetTag,
+	pub value: f64,
+	pub shares: Option<f64>,
+}
+
+pub fn deposit(pot: &impl Pot, prices: &HashMap<AssetTag, f64>, amount: f64) -> Result<Vec<Buy>, PotError> {
+	let portions = pot.read_ladder()?.target_portions();
+	let market_values = pot.read_market_values(prices)?;
+	let mut targets = portions.into_iter()
+		.map(|(asset, portion)| {
+			let value = market_values.get(&asset).cloned().unwrap_or(0.0);
+			(asset, portion, value)
+		})
+		.collect::<Vec<_>>();
+	targets.sort_by(|a, b| a.0.cmp(&b.0));
+	let fills = water_fill(&targets.iter().map(|(_, portion, value)| (*portion, *value)).collect::<Vec<_>>(), amount);
+	let mut buys = Vec::new();
+	for ((asset, _, _), fill) in targets.into_iter().zip(fills) {
+		if fill <= 0.0 {
+			continue;
+		}
+		let buy = match prices.get(&asset) {
+			Some(price) if !asset.is_subpot() && *price > 0.0 => {
+				let shares = asset.floor_shares(fill / price);
+				Buy { value: shares * price, shares: Some(shares), asset }
+			}
+			_ => Buy { value: fill, shares: None, asset },
+		};
+		if buy.value > 0.0 {
+			buys.push(buy);
+		}
+	}
+	Ok(buys)
+}
+
+// Raises a common level so each target buys up to level * portion without selling,
+// spending exactly the amount.
+fn water_fill(targets: &[(f64, f64)], amount: f64) -> Vec<f64> {
+	let spend = |level: f64| -> f64 {
+		targets.iter().map(|(portion, value)| (level * portion - value).max(0.0)).sum()
+	};
+	let total_portion: f64 = targets.iter().map(|(portion, _)| portion).sum();
+	if amount <= 0.0 || total_portion <= 0.0 {
+		return vec![0.0; targets.len()];
+	}
+	let (mut low, mut high) = (0.0, 1.0);
+	while spend(high) < amount {
+		high *= 2.0;
+	}
+	for _ in 0..100 {
+		let level = (low + high) / 2.0;
+		if spend(level) < amount { low = level } else { high = level }
+	}
+	targets.iter().map(|(portion, value)| (high * portion - value).max(0.0)).collect()
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum LotPreference { Oldest, Losses, LongTerm }
+
+impl LotPreference {
+	pub fn parse(s: &str) -> Option<Self> {
+		match s.trim().to_lowercase().as_str() {
+			"oldest" | "fifo" => Some(LotPreference::Oldest),
+			"losses" | "loss" => Some(LotPreference::Losses),
+			"long-term" | "long" => Some(LotPreference::LongTerm),
+			_ => None,
+		}
+	}
+	fn order(&self, lots: &mut Vec<&Lot>, date: NaiveDate) {
+		let basis = |lot: &Lot| lot.basis_per_share().unwrap_or(0.0);
+		let is_long_term = |lot: &Lot| is_long_term(lot, date);
+		lots.sort_by(|a, b| match self {
+			LotPreference::Oldest => a.acquired.cmp(&b.acquired),
+			LotPreference::Losses => basis(b).partial_cmp(&basis(a)).unwrap_or(Ordering::Equal),
+			LotPreference::LongTerm => is_long_term(b).cmp(&is_long_term(a))
+				.then(basis(b).partial_cmp(&basis(a)).unwrap_or(Ordering::Equal)),
+		});
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Sell {
+	pub lot: Lot,
+	pub shares: f64,
+	pub value: f64,
+	pub gain: Option<f64>,
+	pub long_term: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Withdrawal {
+	pub cash: f64,
+	pub sells: Vec<Sell>,
+}
+
+impl Withdrawal {
+	pub fn total(&self) -> f64 { self.cash + self.sells.iter().map(|sell| sell.value).sum::<f64>() }
+}
+
+pub fn withdraw(
+	pot: &impl Pot, prices: &HashMap<AssetTag, f64>, amount: f64,
+	preference: LotPreference, date: NaiveDate,
+) -> Result<Withdrawal, PotError> {
+	let base_currency = pot.read_config()?.base_currency();
+	let cash = pot.read_cash(&base_currency)?.max(0.0).min(amount);
+	let mut withdrawal = Withdrawal { cash, sells: Vec::new() };
+	let need = amount - cash;
+	if need <= 0.0 {
+		return Ok(withdrawal);
+	}
+	let portions = pot.read_ladder()?.target_portions();
+	let lots = pot.read_lots()?;
+	let mut values: HashMap<AssetTag, f64> = HashMap::new();
+	for (lot, value) in pot.read_portfolio()?.lot_values(&local_prices(pot, prices)?)? {
+		if !lot.asset_tag.is_subpot() {
+			*values.entry(lot.asset_tag.clone()).or_insert(0.0) += value;
+		}
+	}
+	let mut assets = values.into_iter().collect::<Vec<_>>();
+	assets.sort_by(|a, b| a.0.cmp(&b.0));
+	let drains = drain(
+		&assets.iter().map(|(asset, value)| (portions.get(asset).cloned().unwrap_or(0.0), *value)).collect::<Vec<_>>(),
+		need,
+	);
+	for ((asset, _), drain) in assets.into_iter().zip(drains) {
+		let price = prices.get(&asset).cloned().unwrap_or(0.0);
+		if drain <= 0.0 || price <= 0.0 {
+			continue;
+		}
+		let mut candidates = lots.iter().filter(|lot| lot.asset_tag == asset && lot.share_count.as_f64() > 0.0).collect::<Vec<_>>();
+		preference.order(&mut candidates, date);
+		let mut remaining = drain / price;
+		for lot in candidates {
+			let shares = asset.round_shares(lot.share_count.as_f64().min(remaining));
+			if shares <= 0.0 {
+				break;
+			}
+			remaining -= shares;
+			withdrawal.sells.push(Sell {
+				lot: lot.clone(),
+				shares,
+				value: shares * price,
+				gain: lot.basis_per_share().map(|basis| shares * (price - basis)),
+				long_term: is_long_term(lot, date),
+			});
+		}
+	}
+	Ok(withdrawal)
+}
+
+// The pots a withdrawal draws from, root first and then sub-pots by path,
+// reordered by the names in `priority` where "." stands for the root.
+pub fn drain_order<P: Pot>(pot: &P, priority: &[&str]) -> Result<Vec<(AssetTag, P)>, PotError> {
+	let mut subpots = pot.read_deep_subpots()?.into_iter().map(|(tag, subpot)| (tag, *subpot)).collect::<Vec<_>>();
+	subpots.sort_by(|a, b| a.1.pot_path().cmp(b.1.pot_path()));
+	let mut pots = vec![(AssetTag::from(":"), pot.clone())];
+	pots.extend(subpots);
+	let rank = |tag: &AssetTag| {
+		let name = if tag.as_str() == ":" { "." } else { tag.as_str() };
+		priority.iter().position(|it| it.eq_ignore_ascii_case(name)).unwrap_or(priority.len())
+	};
+	pots.sort_by_key(|(tag, _)| rank(tag));
+	Ok(pots)
+}
+
+fn is_long_term(lot: &Lot, date: NaiveDate) -> bool {
+	lot.acquired.iter().any(|acquired| date::is_long_term(*acquired, date))
+}
+
+// Lowers a common level so each asset sells down to level * portion, taking
+// off-target assets first and raising exactly the amount when the pot holds it.
+fn drain(assets: &[(f64, f64)], amount: f64) -> Vec<f64> {
+	let raise = |level: f64| -> f64 {
+		assets.iter().map(|(portion, value)| (value - level * portion).max(0.0)).sum()
+	};
+	let off_target: f64 = assets.iter().filter(|(portion, _)| *portion <= 0.0).map(|(_, value)| value).sum();
+	if amount <= off_target {
+		let scale = if off_target > 0.0 { amount / off_target } else { 0.0 };
+		return assets.iter().map(|(portion, value)| if *portion <= 0.0 { value * scale } else { 0.0 }).collect();
+	}
+	let mut high = assets.iter()
+		.filter(|(portion, _)| *portion > 0.0)
+		.map(|(portion, value)| value / portion)
+		.fold(0.0, f64::max);
+	let mut low = 0.0;
+	for _ in 0..100 {
+		let level = (low + high) / 2.0;
+		if raise(level) > amount { low = level } else { high = level }
+	}
+	assets.iter().map(|(portion, value)| (value - low * portion).max(0.0)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::plan::{drain, drain_order, water_fill};
+	use crate::testing::{add_subpot, temp_pot};
+
+	#[test]
+	fn fills_the_most_underweight_targets_first() {
+		let fills = water_fill(&[(0.5, 100.0), (0.5, 0.0)], 50.0);
+		assert!((fills[0] - 0.0).abs() < 1e-6);
+		assert!((fills[1] - 50.0).abs() < 1e-6);
+		let fills = water_fill(&[(0.5, 100.0), (0.5, 0.0)], 300.0);
+		assert!((fills[0] - 100.0).abs() < 1e-6);
+		assert!((fills[1] - 200.0).abs() < 1e-6);
+	}
+
+	#[test]
+	fn drains_off_target_and_overweight_assets_first() {
+		let drains = drain(&[(0.5, 300.0), (0.5, 100.0), (0.0, 50.0)], 40.0);
+		assert_eq!(drains, vec![0.0, 0.0, 40.0]);
+		let drains = drain(&[(0.5, 300.0), (0.5, 100.0), (0.0, 50.0)], 250.0);
+		assert!((drains[0] - 200.0).abs() < 1e-6);
+		assert!(drains[1].abs() < 1e-6);
+		assert!((drains[2] - 50.0).abs() < 1e-6);
+	}
+
+	#[test]
+	fn drains_subpots_in_path_order_unless_prioritized() {
+		let (_dir, mut pot) = temp_pot("drain-order");
+		let mut zed = add_subpot(&mut pot, "zed", vec![]);
+		add_subpot(&mut pot, "alpha", vec![]);
+		add_subpot(&mut zed, "kid", vec![]);
+		add_subpot(&mut pot, "mid", vec![]);
+		let names = |priority: &[&str]| drain_order(&pot, priority).unwrap().into_iter()
+			.map(|(tag, _)| tag.as_str().to_string())
+			.collect::<Vec<_>>();
+		assert_eq!(names(&[]), vec![":", ":alpha", ":mid", ":zed", ":kid"]);
+		assert_eq!(names(&[":mid", "."]), vec![":mid", ":", ":alpha", ":zed", ":kid"]);
+	}
+}
@@ -1,11 +1,12 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use crate::asset_tag::AssetTag;
+use crate::error::PotError;
 use crate::lot::Lot;
 
 pub struct Portfolio {
 	pub lots: Vec<Lot>,
-	pub free_cash: f64,
+	pub free_cash: BTreeMap<AssetTag, f64>,
 }
 
 impl Portfolio {
@@ -14,7 +15,7 @@ impl Portfolio {
 			.iter()
 			.map(Lot::symbol_string)
 			.collect::<HashSet<_>>();
-		set.insert(AssetTag::Usd);
+		set.extend(self.free_cash.keys().cloned());
 		set
 	}
 	pub fn share_counts(&self) -> HashMap<AssetTag, f64> {
@@ -25,31 +26,42 @@ impl Portfolio {
 			let next = previous + lot.share_count.as_f64();
 			map.insert(asset_tag.clone(), next);
 		}
-		map.insert(AssetTag::Usd, self.free_cash);
+		for (currency, amount) in &self.free_cash {
+			*map.entry(currency.clone()).or_insert(0.0) += amount;
+		}
 		map
 	}
-	pub fn market_values(&self, prices: &HashMap<AssetTag, f64>) -> HashMap<AssetTag, f64> {
+	pub fn market_values(&self, prices: &HashMap<AssetTag, f64>) -> Result<HashMap<AssetTag, f64>, PotError> {
 		let share_counts = self.share_counts();
-		let mut map = share_counts
+		share_counts
 			.into_iter()
 			.map(|(asset, count)| market_value(asset, count, prices))
-			.collect::<HashMap<AssetTag, _>>();
-		map.insert(AssetTag::Usd, self.free_cash);
-		map
+			.collect::<Result<HashMap<AssetTag, _>, _>>()
 	}
-	pub fn market_value(&self, prices: &HashMap<AssetTag, f64>) -> f64 {
-		self.market_values(prices)
-			.into_iter()
-			.map(|(_, value)| value)
-			.sum()
+	pub fn lot_values(&self, prices: &HashMap<AssetTag, f64>) -> Result<Vec<(&Lot, f64)>, PotError> {
+		self.lots.iter()
+			.map(|lot| Ok((lot, market_value(lot.asset_tag.clone(), lot.share_count.as_f64(), prices)?.1)))
+			.collect()
+	}
+	pub fn cash_value(&self, prices: &HashMap<AssetTag, f64>) -> Result<f64, PotError> {
+		let mut sum = 0.0;
+		for (currency, amount) in &self.free_cash {
+			sum += market_value(currency.clone(), *amount, prices)?.1;
+		}
+		Ok(sum)
+	}
+	pub fn market_value(&self, prices: &HashMap<AssetTag, f64>) -> Result<f64, PotError> {
+		let value = self.market_values(prices)?.values().sum();
+		Ok(value)
 	}
 }
 
-fn market_value(asset: AssetTag, count: f64, prices: &HashMap<AssetTag, f64>) -> (AssetTag, f64) {
-	if count > 0.0 {
-		let price = prices.get(&asset).cloned().expect("price");
-		(asset, price * count)
+fn market_value(asset: AssetTag, count: f64, prices: &HashMap<AssetTag, f64>) -> Result<(AssetTag, f64), PotError> {
+	if count > 0.0 || (asset.is_currency() && count != 0.0) {
+		let price = prices.get(&asset).cloned()
+			.ok_or_else(|| PotError::pricing(asset.as_str(), "no price available"))?;
+		Ok((asset, price * count))
 	} else {
-		(asset, 0.0)
+		Ok((asset, 0.0))
 	}
 }
@@ -1,51 +1,172 @@
-use std::collections::{HashMap, HashSet};
-use std::error::Error;
-use std::fs::File;
-use std::io::{Read, Write};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 
+use chrono::NaiveDate;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{Custodian, ShareCount};
+use crate::action::CorporateAction;
 use crate::asset_tag::AssetTag;
+use crate::config::PotConfig;
 use crate::core::{DeepAsset, PotPath, Ramp};
 use crate::disk;
+use crate::error::PotError;
+use crate::format::{FORMAT_VERSION, Manifest};
+use crate::income::Income;
 use crate::ladder::Ladder;
 use crate::lot::Lot;
+use crate::metadata::AssetMetadata;
 use crate::portfolio::Portfolio;
+use crate::sale::{self, ReliefMethod, Sale};
+use crate::snapshot::Snapshot;
+
+// A kind of history a pot keeps as an ordered list of rows, stored under its name.
+pub trait Record: Clone + Serialize + DeserializeOwned {
+	const NAME: &'static str;
+}
 
 pub trait Pot: Clone {
 	fn is_not_initialized(&self) -> bool;
-	fn init_if_not(&mut self) -> Result<(), Box<dyn Error>>;
-	fn init(&mut self) -> Result<(), Box<dyn Error>>;
+	fn init_if_not(&mut self) -> Result<(), PotError>;
+	fn init(&mut self) -> Result<(), PotError>;
 	fn subpot(&self, name: &str) -> Box<Self>;
+	fn parent(&self) -> Option<Self>;
+	fn pot_path(&self) -> &PotPath;
 
-	fn read_cash(&self) -> Result<f64, Box<dyn Error>>;
-	fn write_cash(&self, value: f64) -> Result<(), Box<dyn Error>>;
+	fn read_cash_balances(&self) -> Result<BTreeMap<AssetTag, f64>, PotError>;
+	fn write_cash_balances(&self, balances: &BTreeMap<AssetTag, f64>) -> Result<(), PotError>;
 
-	fn read_ramp(&self) -> Result<Ramp, Box<dyn Error>>;
-	fn write_ramp(&self, ramp: Ramp) -> Result<(), Box<dyn Error>>;
+	fn read_local_config(&self) -> Result<PotConfig, PotError>;
+	fn write_local_config(&self, config: &PotConfig) -> Result<(), PotError>;
 
-	fn read_lots(&self) -> Result<Vec<Lot>, Box<dyn Error>>;
-	fn write_lots(&mut self, lots: &Vec<Lot>) -> Result<(), Box<dyn Error>>;
+	fn read_lots(&self) -> Result<Vec<Lot>, PotError>;
+	fn write_lots(&mut self, lots: &[Lot]) -> Result<(), PotError>;
 
-	fn read_shares(&self, custodian: &str, symbol: &str) -> Result<f64, Box<dyn Error>>;
-	fn write_shares(&mut self, custodian: &str, symbol: &str, count: f64) -> Result<u64, Box<dyn Error>>;
+	fn read_targets(&self) -> Result<Vec<AssetTag>, PotError>;
+	fn write_targets(&self, targets: &[AssetTag]) -> Result<(), PotError>;
 
-	fn read_targets(&self) -> Result<Vec<AssetTag>, Box<dyn Error>>;
-	fn write_targets(&self, targets: &Vec<AssetTag>) -> Result<(), Box<dyn Error>>;
+	fn read_asset_metadata(&self) -> Result<AssetMetadata, PotError>;
+	fn write_asset_metadata(&self, metadata: &AssetMetadata) -> Result<(), PotError>;
 
-	fn read_ladder(&self) -> Result<Ladder, Box<dyn Error>>;
+	fn read_records<R: Record>(&self) -> Result<Vec<R>, PotError>;
+	fn write_records<R: Record>(&self, records: &[R]) -> Result<(), PotError>;
 
-	fn read_lot_assets(&self) -> Result<HashSet<AssetTag>, Box<dyn Error>>;
-	fn read_deep_lot_assets(&self) -> Result<HashSet<AssetTag>, Box<dyn Error>>;
+	fn read_config(&self) -> Result<PotConfig, PotError> {
+		let config = self.read_local_config()?;
+		match self.parent() {
+			None => Ok(config),
+			Some(parent) => Ok(config.inherit(&parent.read_config()?)),
+		}
+	}
 
-	fn read_deep_subpots(&self) -> Result<Vec<(AssetTag, Box<Self>)>, Box<dyn Error>> {
-		let local_subpots = self.read_lot_assets()?
-			.into_iter()
-			.filter(AssetTag::is_subpot)
-			.map(|asset| {
-				let pot = self.subpot(asset.as_folder_name());
-				(asset, pot)
-			})
-			.collect::<Vec<_>>();
+	fn read_cash(&self, currency: &AssetTag) -> Result<f64, PotError> {
+		let balances = self.read_cash_balances()?;
+		Ok(balances.get(currency).cloned().unwrap_or(0.0))
+	}
+	fn write_cash(&self, currency: &AssetTag, value: f64) -> Result<(), PotError> {
+		let mut balances = self.read_cash_balances()?;
+		balances.insert(currency.clone(), value);
+		self.write_cash_balances(&balances)
+	}
+
+	fn read_ramp(&self) -> Result<Ramp, PotError> {
+		Ok(self.read_config()?.ramp())
+	}
+	fn write_ramp(&self, ramp: Ramp) -> Result<(), PotError> {
+		let mut config = self.read_local_config()?;
+		config.ramp = Some(ramp);
+		self.write_local_config(&config)
+	}
+
+	fn read_shares(&self, custodian: &str, symbol: &str) -> Result<f64, PotError> {
+		let tag = AssetTag::from(symbol);
+		let lots = self.read_lots()?;
+		let lot = lots.into_iter().find(|lot| lot.has_tag(&tag) && lot.has_custodian(custodian));
+		let count = if let Some(lot) = lot {
+			lot.share_count.as_f64()
+		} else {
+			0.0
+		};
+		Ok(count)
+	}
+	fn write_shares(&mut self, custodian: &str, symbol: &str, count: f64) -> Result<u64, PotError> {
+		let tag = AssetTag::from(symbol);
+		let mut lot_id: Option<u64> = None;
+		let new_lots = self.read_lots()?.into_iter().map(|lot| {
+			if lot.has_tag(&tag) && lot.has_custodian(custodian) {
+				lot_id = Some(lot.uid);
+				lot.with_share_count(count)
+			} else {
+				lot
+			}
+		}).collect::<Vec<_>>();
+		match lot_id {
+			Some(lot_id) => {
+				self.write_lots(&new_lots)?;
+				Ok(lot_id)
+			}
+			None => Err(PotError::MissingLot { custodian: custodian.to_string(), symbol: tag.as_str().to_string() }),
+		}
+	}
+
+	fn read_ladder(&self) -> Result<Ladder, PotError> {
+		let config = self.read_config()?;
+		Ladder::with_groups(self.read_targets()?, config.ramp(), &config)
+	}
+
+	fn read_lot_assets(&self) -> Result<HashSet<AssetTag>, PotError> {
+		let set = self.read_lots()?
+			.iter()
+			.filter(|lot| lot.share_count.as_f64() > 0.0)
+			.map(|lot| &lot.asset_tag)
+			.cloned()
+			.collect::<HashSet<_>>();
+		Ok(set)
+	}
+
+	fn read_deep_lot_assets(&self) -> Result<HashSet<AssetTag>, PotError> {
+		let top_assets = self.read_lot_assets()?;
+		let (sub_tags, top_tags): (Vec<AssetTag>, Vec<AssetTag>) = top_assets.into_iter().partition(AssetTag::is_subpot);
+		let mut deep_assets = HashSet::new();
+		deep_assets.extend(top_tags);
+		for tag in sub_tags {
+			let subpot = self.subpot(tag.as_folder_name()?);
+			let subpot_assets = subpot.read_deep_lot_assets()?;
+			deep_assets.extend(subpot_assets);
+		}
+		Ok(deep_assets)
+	}
+
+	fn read_deep_assets(&self) -> Result<HashSet<DeepAsset>, PotError> {
+		let local_assets = self.read_lot_assets()?;
+		let (sub_tags, leaf_tags): (Vec<AssetTag>, Vec<AssetTag>) = local_assets.into_iter().partition(AssetTag::is_subpot);
+		let mut deep_assets = leaf_tags.iter()
+			.map(|it| DeepAsset::new(self.pot_path(), it))
+			.collect::<HashSet<_>>();
+		for tag in sub_tags {
+			let subpot = self.subpot(tag.as_folder_name()?);
+			let subpot_assets = subpot.read_deep_assets()?;
+			deep_assets.extend(subpot_assets);
+		}
+		Ok(deep_assets)
+	}
+
+	fn read_deep_cash_currencies(&self) -> Result<HashSet<AssetTag>, PotError> {
+		let mut currencies = self.read_cash_balances()?.into_keys().collect::<HashSet<_>>();
+		for (_, subpot) in self.read_deep_subpots()? {
+			currencies.extend(subpot.read_cash_balances()?.into_keys());
+		}
+		Ok(currencies)
+	}
+
+	fn read_deep_subpots(&self) -> Result<Vec<(AssetTag, Box<Self>)>, PotError> {
+		let mut local_subpots = Vec::new();
+		for asset in self.read_lot_assets()?.into_iter().filter(AssetTag::is_subpot) {
+			let pot = self.subpot(asset.as_folder_name()?);
+			local_subpots.push((asset, pot));
+		}
 		let mut subpots = Vec::new();
 		subpots.extend(local_subpots.clone());
 		for (_, pot) in local_subpots {
@@ -55,43 +176,133 @@ pub trait Pot: Clone {
 		Ok(subpots)
 	}
 
-	fn read_market_value(&self, prices: &HashMap<AssetTag, f64>) -> Result<f64, Box<dyn Error>> {
+	fn read_portfolio(&self) -> Result<Portfolio, PotError> {
 		let portfolio = Portfolio {
 			lots: self.read_lots()?,
-			free_cash: self.read_cash()?,
+			free_cash: self.read_cash_balances()?,
 		};
-		let value = portfolio.market_value(prices);
-		Ok(value)
+		Ok(portfolio)
 	}
-	fn read_market_values(&self, prices: &HashMap<AssetTag, f64>) -> Result<HashMap<AssetTag, f64>, Box<dyn Error>> {
-		let portfolio = Portfolio {
-			lots: self.read_lots()?,
-			free_cash: self.read_cash()?,
-		};
-		let value = portfolio.market_values(prices);
-		Ok(value)
+	fn read_market_value(&self, prices: &HashMap<AssetTag, f64>) -> Result<f64, PotError> {
+		self.read_portfolio()?.market_value(prices)
+	}
+	fn read_market_values(&self, prices: &HashMap<AssetTag, f64>) -> Result<HashMap<AssetTag, f64>, PotError> {
+		self.read_portfolio()?.market_values(prices)
 	}
-	fn add_lots(&mut self, additions: Vec<Lot>) -> Result<(), Box<dyn Error>> {
+	fn add_lots(&mut self, additions: Vec<Lot>) -> Result<(), PotError> {
 		let mut lots = self.read_lots()?;
 		lots.extend(additions);
 		self.write_lots(&lots)
 	}
+	fn sell(&mut self, custodian: &str, asset: &AssetTag, count: f64, method: ReliefMethod, price: f64, date: NaiveDate) -> Result<Vec<Sale>, PotError> {
+		let (lots, new_sales) = sale::sell(&self.read_lots()?, custodian, asset, count, method, price, date)?;
+		let mut sales = self.read_records::<Sale>()?;
+		sales.extend(new_sales.clone());
+		let base_currency = self.read_config()?.base_currency();
+		let proceeds: f64 = new_sales.iter().map(|sale| sale.proceeds).sum();
+		self.write_lots(&lots)?;
+		self.write_records(&sales)?;
+		self.write_cash(&base_currency, self.read_cash(&base_currency)? + proceeds)?;
+		Ok(new_sales)
+	}
+	fn record_income(&mut self, income: Income) -> Result<(), PotError> {
+		match income.reinvested {
+			Some(shares) => self.add_lots(vec![Lot {
+				custodian: income.custodian.clone(),
+				asset_tag: income.asset_tag.clone(),
+				share_count: ShareCount(shares),
+				uid: Lot::random_uid(),
+				basis: Some(income.amount),
+				acquired: Some(income.date),
+			}])?,
+			None => {
+				let base_currency = self.read_config()?.base_currency();
+				self.write_cash(&base_currency, self.read_cash(&base_currency)? + income.amount)?;
+			}
+		}
+		let mut records = self.read_records::<Income>()?;
+		records.push(income);
+		self.write_records(&records)
+	}
+}
+
+// Renames the custodian in every pot of the tree, including the sales and income
+// already recorded under it, and returns the number of lots moved.
+pub fn rename_custodian<P: Pot>(pot: &mut P, old: &str, new: &str) -> Result<usize, PotError> {
+	let mut pots = vec![pot.clone()];
+	pots.extend(pot.read_deep_subpots()?.into_iter().map(|(_, subpot)| *subpot));
+	let mut renamed = 0;
+	for mut pot in pots {
+		let mut lots = pot.read_lots()?;
+		let before = renamed;
+		for lot in lots.iter_mut().filter(|lot| lot.has_custodian(old)) {
+			lot.custodian = Custodian(new.to_string());
+			renamed += 1;
+		}
+		if renamed > before {
+			pot.write_lots(&lots)?;
+		}
+		let mut sales = pot.read_records::<Sale>()?;
+		if sales.iter().any(|sale| sale.custodian.as_str() == old) {
+			for sale in sales.iter_mut().filter(|sale| sale.custodian.as_str() == old) {
+				sale.custodian = Custodian(new.to_string());
+			}
+			pot.write_records(&sales)?;
+		}
+		let mut income = pot.read_records::<Income>()?;
+		if income.iter().any(|record| record.custodian.as_str() == old) {
+			for record in income.iter_mut().filter(|record| record.custodian.as_str() == old) {
+				record.custodian = Custodian(new.to_string());
+			}
+			pot.write_records(&income)?;
+		}
+		let mut config = pot.read_local_config()?;
+		if let Some(custodian) = config.custodians.remove(old) {
+			config.custodians.insert(new.to_string(), custodian);
+			pot.write_local_config(&config)?;
+		}
+	}
+	Ok(renamed)
+}
+
+pub fn copy_pot<S: Pot, D: Pot>(src: &S, dest: &mut D) -> Result<(), PotError> {
+	dest.init_if_not()?;
+	let lots = src.read_lots()?;
+	dest.write_lots(&lots)?;
+	dest.write_cash_balances(&src.read_cash_balances()?)?;
+	dest.write_local_config(&src.read_local_config()?)?;
+	dest.write_targets(&src.read_targets()?)?;
+	dest.write_asset_metadata(&src.read_asset_metadata()?)?;
+	dest.write_records(&src.read_records::<Sale>()?)?;
+	dest.write_records(&src.read_records::<CorporateAction>()?)?;
+	dest.write_records(&src.read_records::<Income>()?)?;
+	dest.write_records(&src.read_records::<Snapshot>()?)?;
+	let subpot_tags = lots.into_iter()
+		.map(|lot| lot.asset_tag)
+		.filter(AssetTag::is_subpot)
+		.collect::<BTreeSet<_>>();
+	for tag in subpot_tags {
+		let src_subpot = src.subpot(tag.as_folder_name()?);
+		let mut dest_subpot = dest.subpot(tag.as_folder_name()?);
+		copy_pot(src_subpot.as_ref(), dest_subpot.as_mut())?;
+	}
+	Ok(())
 }
 
 impl Pot for FolderPot {
 	fn is_not_initialized(&self) -> bool { csv::Reader::from_path(self.lots_file()).is_err() }
-	fn init_if_not(&mut self) -> Result<(), Box<dyn Error>> {
-		std::fs::create_dir_all(self.path.as_path())?;
+	fn init_if_not(&mut self) -> Result<(), PotError> {
+		std::fs::create_dir_all(self.path.as_path()).map_err(|err| PotError::io(&self.path, err))?;
 		if self.is_not_initialized() {
 			self.init()
 		} else {
 			Ok(())
 		}
 	}
-	fn init(&mut self) -> Result<(), Box<dyn Error>> {
+	fn init(&mut self) -> Result<(), PotError> {
+		self.write_format_version(FORMAT_VERSION)?;
 		self.write_lots(&Vec::new())?;
-		self.write_cash(0.0)?;
-		self.write_ramp(Ramp::Golden)?;
+		self.write_cash_balances(&BTreeMap::new())?;
 		Ok(())
 	}
 
@@ -102,121 +313,131 @@ impl Pot for FolderPot {
 			pot_path: self.pot_path.extend(name),
 		})
 	}
+	fn parent(&self) -> Option<Self> {
+		match &self.pot_path {
+			PotPath::CurrentFolder => None,
+			PotPath::SubFolder(parent, _) => Some(FolderPot {
+				path: self.path.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from(".")),
+				pot_path: parent.as_ref().clone(),
+			}),
+		}
+	}
+	fn pot_path(&self) -> &PotPath { &self.pot_path }
 
-	fn read_cash(&self) -> Result<f64, Box<dyn Error>> {
-		disk::read_f64(&self.cash_file())
+	fn read_cash_balances(&self) -> Result<BTreeMap<AssetTag, f64>, PotError> {
+		let cash_file = self.cash_file();
+		let string = disk::read_string(&cash_file).map_err(|err| self.missing_pot_or(err))?;
+		let mut balances = BTreeMap::new();
+		for (index, line) in string.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+			let parse_error = |message| PotError::parse(&cash_file, Some(index as u64 + 1), message);
+			let mut parts = line.split_whitespace();
+			let code = parts.next().unwrap_or("");
+			let currency = AssetTag::parse_currency(code)
+				.ok_or_else(|| parse_error(format!("unknown currency {}", code)))?;
+			let amount = parts.next()
+				.ok_or_else(|| parse_error("missing amount".to_string()))?
+				.parse::<f64>()
+				.map_err(|err| parse_error(err.to_string()))?;
+			balances.insert(currency, amount);
+		}
+		Ok(balances)
 	}
 
-	fn write_cash(&self, value: f64) -> Result<(), Box<dyn Error>> {
-		let value_s = value.to_string();
-		disk::write_string(&self.cash_file(), &value_s)
+	fn write_cash_balances(&self, balances: &BTreeMap<AssetTag, f64>) -> Result<(), PotError> {
+		let lines = balances.iter()
+			.map(|(currency, amount)| format!("{} {}\n", currency.symbol(), amount))
+			.collect::<String>();
+		disk::write_string(&self.cash_file(), &lines)
 	}
 
-	fn read_ramp(&self) -> Result<Ramp, Box<dyn Error>> {
-		let string = disk::read_string(&self.ramp_file()).unwrap_or("golden".to_string());
-		let ramp = Ramp::from_str(&string);
-		Ok(ramp)
+	fn read_local_config(&self) -> Result<PotConfig, PotError> {
+		Ok(self.read_manifest()?.config)
 	}
 
-	fn write_ramp(&self, ramp: Ramp) -> Result<(), Box<dyn Error>> {
-		disk::write_string(&self.ramp_file(), ramp.as_str())
+	fn write_local_config(&self, config: &PotConfig) -> Result<(), PotError> {
+		let manifest = Manifest { config: config.clone(), ..self.read_manifest()? };
+		disk::write_toml(&self.manifest_file(), &manifest)
 	}
 
-	fn read_lots(&self) -> Result<Vec<Lot>, Box<dyn Error>> {
+	fn read_lots(&self) -> Result<Vec<Lot>, PotError> {
+		let lots_file = self.lots_file();
 		let mut lots = Vec::new();
-		let mut rdr = csv::Reader::from_path(self.lots_file())?;
+		let mut rdr = csv::Reader::from_path(&lots_file)
+			.map_err(|err| self.missing_pot_or(PotError::csv(&lots_file, err)))?;
 		for result in rdr.deserialize() {
-			let lot: Lot = result?;
+			let lot: Lot = result.map_err(|err| PotError::csv(&lots_file, err))?;
 			lots.insert(0, lot);
 		}
 		lots.reverse();
 		Ok(lots)
 	}
-	fn write_lots(&mut self, lots: &Vec<Lot>) -> Result<(), Box<dyn Error>> {
-		let mut wtr = csv::Writer::from_path(self.lots_file())?;
+	fn write_lots(&mut self, lots: &[Lot]) -> Result<(), PotError> {
+		let lots_file = self.lots_file();
+		let mut wtr = csv::Writer::from_path(&lots_file).map_err(|err| PotError::csv(&lots_file, err))?;
 		for lot in lots {
-			wtr.serialize(lot)?;
+			wtr.serialize(lot).map_err(|err| PotError::csv(&lots_file, err))?;
 		}
-		wtr.flush()?;
-		Ok(())
-	}
-
-	fn read_shares(&self, custodian: &str, symbol: &str) -> Result<f64, Box<dyn Error>> {
-		let tag = AssetTag::from(symbol);
-		let lots = self.read_lots()?;
-		let lot = lots.into_iter().find(|lot| lot.has_tag(&tag) && lot.has_custodian(custodian));
-		let count = if let Some(lot) = lot {
-			lot.share_count.as_f64()
-		} else {
-			0.0
-		};
-		Ok(count)
-	}
-	fn write_shares(&mut self, custodian: &str, symbol: &str, count: f64) -> Result<u64, Box<dyn Error>> {
-		let tag = AssetTag::from(symbol);
-		let mut lot_id: Option<u64> = None;
-		let new_lots = self.read_lots()?.into_iter().map(|lot| {
-			if lot.has_tag(&tag) && lot.has_custodian(custodian) {
-				lot_id = Some(lot.uid);
-				lot.with_share_count(count)
-			} else {
-				lot
-			}
-		}).collect::<Vec<_>>();
-		self.write_lots(&new_lots)?;
-		Ok(lot_id.expect("lot it"))
+		wtr.flush().map_err(|err| PotError::io(&lots_file, err))
 	}
 
-	fn read_targets(&self) -> Result<Vec<AssetTag>, Box<dyn Error>> {
-		let mut file_s = String::new();
-		let file_open = File::open(self.team_file());
-		if file_open.is_err() {
+	fn read_targets(&self) -> Result<Vec<AssetTag>, PotError> {
+		let team_file = self.team_file();
+		if !team_file.exists() {
 			Ok(Vec::new())
 		} else {
-			file_open?.read_to_string(&mut file_s)?;
+			let file_s = disk::read_string(&team_file)?;
 			let asset_tags = file_s
 				.split("\n")
-				.into_iter()
 				.filter(|s| !s.trim().is_empty())
 				.map(|s| AssetTag::from(s.trim()))
 				.collect::<Vec<_>>();
 			Ok(asset_tags)
 		}
 	}
-	fn write_targets(&self, targets: &Vec<AssetTag>) -> Result<(), Box<dyn Error>> {
+	fn write_targets(&self, targets: &[AssetTag]) -> Result<(), PotError> {
 		let symbols = targets.iter().map(|tag| tag.as_str().to_string()).collect::<Vec<String>>();
 		let targets: String = symbols.join("\n");
-		let mut file = File::create(self.team_file())?;
-		file.write_all(targets.as_bytes())?;
-		Ok(())
+		disk::write_string(&self.team_file(), &targets)
 	}
 
-	fn read_ladder(&self) -> Result<Ladder, Box<dyn Error>> {
-		let ladder = Ladder { targets: self.read_targets()?, ramp: self.read_ramp()? };
-		Ok(ladder)
+	fn read_asset_metadata(&self) -> Result<AssetMetadata, PotError> {
+		let assets_file = self.assets_file();
+		if !assets_file.exists() {
+			Ok(AssetMetadata::default())
+		} else {
+			disk::read_toml(&assets_file)
+		}
 	}
-
-	fn read_lot_assets(&self) -> Result<HashSet<AssetTag>, Box<dyn Error>> {
-		let set = self.read_lots()?
-			.iter()
-			.filter(|lot| lot.share_count.as_f64() > 0.0)
-			.map(|lot| &lot.asset_tag)
-			.cloned()
-			.collect::<HashSet<_>>();
-		Ok(set)
+	fn write_asset_metadata(&self, metadata: &AssetMetadata) -> Result<(), PotError> {
+		let assets_file = self.assets_file();
+		if metadata.is_empty() && !assets_file.exists() {
+			Ok(())
+		} else {
+			disk::write_toml(&assets_file, metadata)
+		}
 	}
 
-	fn read_deep_lot_assets(&self) -> Result<HashSet<AssetTag>, Box<dyn Error>> {
-		let top_assets = self.read_lot_assets()?;
-		let (sub_tags, top_tags): (Vec<AssetTag>, Vec<AssetTag>) = top_assets.into_iter().partition(AssetTag::is_subpot);
-		let mut deep_assets = HashSet::new();
-		deep_assets.extend(top_tags);
-		for tag in sub_tags {
-			let subpot = self.subpot(tag.as_folder_name());
-			let subpot_assets = subpot.read_deep_lot_assets()?;
-			deep_assets.extend(subpot_assets);
+	fn read_records<R: Record>(&self) -> Result<Vec<R>, PotError> {
+		let records_file = self.records_file::<R>();
+		let mut records = Vec::new();
+		if records_file.exists() {
+			let mut rdr = csv::Reader::from_path(&records_file).map_err(|err| PotError::csv(&records_file, err))?;
+			for result in rdr.deserialize() {
+				records.push(result.map_err(|err| PotError::csv(&records_file, err))?);
+			}
 		}
-		Ok(deep_assets)
+		Ok(records)
+	}
+	fn write_records<R: Record>(&self, records: &[R]) -> Result<(), PotError> {
+		let records_file = self.records_file::<R>();
+		if records.is_empty() && !records_file.exists() {
+			return Ok(());
+		}
+		let mut wtr = csv::Writer::from_path(&records_file).map_err(|err| PotError::csv(&records_file, err))?;
+		for record in records {
+			wtr.serialize(record).map_err(|err| PotError::csv(&records_file, err))?;
+		}
+		wtr.flush().map_err(|err| PotError::io(&records_file, err))
 	}
 }
 
@@ -226,6 +447,10 @@ pub struct FolderPot {
 	pot_path: PotPath,
 }
 
+impl Default for FolderPot {
+	fn default() -> Self { FolderPot::new() }
+}
+
 impl FolderPot {
 	pub fn new() -> Self {
 		FolderPot {
@@ -233,6 +458,12 @@ impl FolderPot {
 			pot_path: PotPath::CurrentFolder,
 		}
 	}
+	pub fn from_path(path: &Path) -> Self {
+		FolderPot {
+			path: path.to_owned(),
+			pot_path: PotPath::CurrentFolder,
+		}
+	}
 	pub fn from_pot_path(pot_path: &PotPath) -> Self {
 		let path = pot_path.segment_names()
 			.into_iter()
@@ -244,23 +475,103 @@ impl FolderPot {
 		FolderPot { path, pot_path: pot_path.to_owned() }
 	}
 	pub fn path(&self) -> &Path { &self.path }
-	pub fn read_deep_assets(&self) -> Result<HashSet<DeepAsset>, Box<dyn Error>> {
-		let local_assets = self.read_lot_assets()?;
-		let (sub_tags, leaf_tags): (Vec<AssetTag>, Vec<AssetTag>) = local_assets.into_iter().partition(AssetTag::is_subpot);
-		let mut deep_assets = leaf_tags.iter()
-			.map(|it| DeepAsset::new(&self.pot_path, it))
-			.collect::<HashSet<_>>();
-		for tag in sub_tags {
-			let subpot = self.subpot(tag.as_folder_name());
-			let subpot_assets = subpot.read_deep_assets()?;
-			deep_assets.extend(subpot_assets);
+	pub fn read_format_version(&self) -> Result<u32, PotError> {
+		Ok(self.read_manifest()?.format)
+	}
+	pub fn write_format_version(&self, format: u32) -> Result<(), PotError> {
+		let manifest = Manifest { format, ..self.read_manifest()? };
+		disk::write_toml(&self.manifest_file(), &manifest)
+	}
+	fn read_manifest(&self) -> Result<Manifest, PotError> {
+		let manifest_file = self.manifest_file();
+		if !manifest_file.exists() {
+			Ok(Manifest { format: 0, config: PotConfig::default() })
+		} else {
+			disk::read_toml(&manifest_file)
+		}
+	}
+	fn missing_pot_or(&self, err: PotError) -> PotError {
+		match err {
+			PotError::Io { ref source, .. } if source.kind() == ErrorKind::NotFound => {
+				PotError::MissingPot { path: self.path.clone() }
+			}
+			err => err,
 		}
-		Ok(deep_assets)
 	}
-
 	fn file_path(&self, filename: &str) -> PathBuf { self.path.join(filename) }
 	fn cash_file(&self) -> PathBuf { self.file_path("cash.txt") }
-	fn ramp_file(&self) -> PathBuf { self.file_path("ramp.txt") }
 	fn lots_file(&self) -> PathBuf { self.file_path("lots.csv") }
 	fn team_file(&self) -> PathBuf { self.file_path("team.txt") }
+	fn assets_file(&self) -> PathBuf { self.file_path("assets.toml") }
+	fn records_file<R: Record>(&self) -> PathBuf { self.file_path(&format!("{}.csv", R::NAME)) }
+	fn manifest_file(&self) -> PathBuf { self.file_path("pot.toml") }
+}
+
+#[cfg(test)]
+mod tests {
+	use chrono::NaiveDate;
+
+	use crate::Custodian;
+	use crate::asset_tag::AssetTag;
+	use crate::config::CustodianConfig;
+	use crate::error::PotError;
+	use crate::income::Income;
+	use crate::pot::{Pot, rename_custodian};
+	use crate::sale::Sale;
+	use crate::testing::{add_subpot, lot, temp_pot};
+
+	#[test]
+	fn renames_custodian_in_sales_and_income_across_the_tree() {
+		let (_dir, mut pot) = temp_pot("rename");
+		pot.write_lots(&[lot("ira", "VTI", 1.0)]).unwrap();
+		let kid = add_subpot(&mut pot, "kid", vec![]);
+		let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+		let mut config = pot.read_local_config().unwrap();
+		config.custodians.insert("ira".to_string(), CustodianConfig::default());
+		pot.write_local_config(&config).unwrap();
+		kid.write_records(&[Sale {
+			uid: 1,
+			custodian: Custodian("ira".to_string()),
+			asset_tag: AssetTag::from("VTI"),
+			count: 1.0,
+			acquired: None,
+			sold: date,
+			proceeds: 100.0,
+			basis: None,
+		}]).unwrap();
+		kid.write_records(&[Income {
+			date,
+			custodian: Custodian("ira".to_string()),
+			asset_tag: AssetTag::from("VTI"),
+			amount: 2.0,
+			reinvested: None,
+		}]).unwrap();
+		assert_eq!(rename_custodian(&mut pot, "ira", "roth").unwrap(), 1);
+		assert!(pot.read_local_config().unwrap().custodians.contains_key("roth"));
+		assert_eq!(pot.read_lots().unwrap()[0].custodian.as_str(), "roth");
+		assert_eq!(kid.read_records::<Sale>().unwrap()[0].custodian.as_str(), "roth");
+		assert_eq!(kid.read_records::<Income>().unwrap()[0].custodian.as_str(), "roth");
+	}
+
+	#[test]
+	fn bare_currency_like_symbols_load_as_equities() {
+		let (_dir, pot) = temp_pot("nok");
+		std::fs::write(pot.path().join("lots.csv"), "custody,symbol,count\nmain,NOK,10\n").unwrap();
+		std::fs::write(pot.path().join("team.txt"), "NOK\nHKD").unwrap();
+		std::fs::write(pot.path().join("cash.txt"), "USD 5\nNOK 100\n").unwrap();
+		assert_eq!(pot.read_lots().unwrap()[0].asset_tag, AssetTag::equity("NOK"));
+		assert_eq!(pot.read_targets().unwrap(), vec![AssetTag::equity("NOK"), AssetTag::equity("HKD")]);
+		assert_eq!(pot.read_cash(&AssetTag::currency("NOK")).unwrap(), 100.0);
+		pot.write_cash(&AssetTag::currency("EUR"), 2.0).unwrap();
+		assert_eq!(std::fs::read_to_string(pot.path().join("cash.txt")).unwrap(), "EUR 2\nNOK 100\nUSD 5\n");
+	}
+
+	#[test]
+	fn missing_lots_and_subpots_are_errors_not_panics() {
+		let (_dir, mut pot) = temp_pot("errors");
+		assert!(matches!(pot.write_shares("main", "VTI", 3.0), Err(PotError::MissingLot { .. })));
+		assert!(matches!(AssetTag::from("VTI").as_folder_name(), Err(PotError::NotAPot { .. })));
+		let missing = *pot.subpot("missing");
+		assert!(matches!(missing.read_lots(), Err(PotError::MissingPot { .. })));
+	}
 }
@@ -0,0 +1,214 @@
+use std::collections::{HashMap, HashSet};
+
+use smarket::yf::PricingResult;
+
+use crate::asset_tag::AssetTag;
+use crate::config::{PotConfig, PriceProvider};
+use crate::core::PotPath;
+use crate::error::PotError;
+use crate::pot::Pot;
+
+pub fn fetch_prices(pot: &impl Pot) -> Result<HashMap<AssetTag, f64>, PotError> {
+	fetch_prices_with(pot, HashSet::new())
+}
+
+// Prices the pot's deep holdings along with extra assets it may not hold.
+pub fn fetch_prices_with(pot: &impl Pot, extra: HashSet<AssetTag>) -> Result<HashMap<AssetTag, f64>, PotError> {
+	let config = pot.read_config()?;
+	let base = config.base_currency();
+	let mut held = pot.read_deep_lot_assets()?;
+	held.extend(extra.into_iter().filter(|asset| !asset.is_subpot() && !asset.is_group()));
+	let (currencies, assets): (HashSet<AssetTag>, HashSet<AssetTag>) = held
+		.into_iter()
+		.partition(AssetTag::is_currency);
+	let quote_prices = {
+		let mut quote_prices = HashMap::new();
+		if config.prices.provider() == PriceProvider::Yahoo {
+			let unquoted = assets.iter()
+				.filter(|asset| !config.prices.quotes.contains_key(asset) && asset.provider_symbol().is_some())
+				.cloned()
+				.collect();
+			quote_prices.extend(fetch_equity_prices(unquoted)?);
+		}
+		for asset in &assets {
+			if let Some(price) = config.prices.quotes.get(asset) {
+				quote_prices.insert(asset.clone(), *price);
+			} else if !asset.is_subpot() && !quote_prices.contains_key(asset) {
+				return Err(PotError::pricing(asset.as_str(), "no manual quote in pot.toml"));
+			}
+		}
+		quote_prices
+	};
+	let usd_rates = {
+		let mut codes = currencies;
+		codes.extend(pot.read_deep_cash_currencies()?);
+		codes.extend(assets.iter().map(|asset| config.prices.quote_currency(asset)));
+		codes.insert(base.clone());
+		fetch_usd_rates(codes, &config)?
+	};
+	let usd_rate = |currency: &AssetTag| usd_rates.get(currency).cloned()
+		.ok_or_else(|| PotError::pricing(currency.as_str(), "no exchange rate available"));
+	let base_rate = usd_rate(&base)?;
+	let mut prices = HashMap::new();
+	for (currency, rate) in &usd_rates {
+		prices.insert(currency.clone(), rate / base_rate);
+	}
+	for (asset, price) in quote_prices {
+		let rate = usd_rate(&config.prices.quote_currency(&asset))?;
+		prices.insert(asset, price * rate / base_rate);
+	}
+	local_prices(pot, &prices)
+}
+
+// Prices for valuing the pot itself: the asset prices with its own sub-pots at
+// their values by path, so same-named sub-pots in other branches never collide.
+pub fn local_prices<P: Pot>(pot: &P, prices: &HashMap<AssetTag, f64>) -> Result<HashMap<AssetTag, f64>, PotError> {
+	subpot_prices(pot, prices, &pot_values(pot, prices)?)
+}
+
+// Values every pot in the tree by pot path, so same-named sub-pots in different
+// branches keep their own values.
+pub fn pot_values<P: Pot>(pot: &P, prices: &HashMap<AssetTag, f64>) -> Result<HashMap<PotPath, f64>, PotError> {
+	let mut values = HashMap::new();
+	add_pot_values(pot, prices, &mut values)?;
+	Ok(values)
+}
+
+// Prices the pot's own sub-pots at their values from pot_values.
+pub fn subpot_prices<P: Pot>(
+	pot: &P, prices: &HashMap<AssetTag, f64>, values: &HashMap<PotPath, f64>,
+) -> Result<HashMap<AssetTag, f64>, PotError> {
+	let mut prices = prices.clone();
+	for tag in pot.read_lot_assets()?.into_iter().filter(AssetTag::is_subpot) {
+		if let Some(value) = values.get(pot.subpot(tag.as_folder_name()?).pot_path()) {
+			prices.insert(tag, *value);
+		}
+	}
+	Ok(prices)
+}
+
+fn add_pot_values<P: Pot>(pot: &P, prices: &HashMap<AssetTag, f64>, values: &mut HashMap<PotPath, f64>) -> Result<f64, PotError> {
+	for tag in pot.read_lot_assets()?.into_iter().filter(AssetTag::is_subpot) {
+		add_pot_values(pot.subpot(tag.as_folder_name()?).as_ref(), prices, values)?;
+	}
+	let value = pot.read_market_value(&subpot_prices(pot, prices, values)?)?;
+	values.insert(pot.pot_path().clone(), value);
+	Ok(value)
+}
+
+fn fetch_usd_rates(currencies: HashSet<AssetTag>, config: &PotConfig) -> Result<HashMap<AssetTag, f64>, PotError> {
+	let mut rates = HashMap::new();
+	let mut unquoted = Vec::new();
+	for currency in currencies {
+		if currency == AssetTag::usd() {
+			rates.insert(currency, 1.0);
+		} else if let Some(rate) = config.prices.quotes.get(&currency) {
+			rates.insert(currency, *rate);
+		} else {
+			unquoted.push(currency);
+		}
+	}
+	if config.prices.provider() == PriceProvider::Yahoo {
+		rates.extend(fetch_equity_prices(unquoted)?);
+	}
+	Ok(rates)
+}
+
+pub fn fetch_equity_prices(equities: Vec<AssetTag>) -> Result<HashMap<AssetTag, f64>, PotError> {
+	let prices_by_asset = if equities.is_empty() {
+		HashMap::new()
+	} else {
+		let assets_by_symbol = equities
+			.iter()
+			.map(|it| (it.provider_symbol().unwrap_or_else(|| it.as_str().to_string()), it.clone()))
+			.collect::<HashMap<String, _>>();
+		let symbols = assets_by_symbol.keys().cloned().collect::<Vec<_>>();
+		let results = smarket::yf::price_assets(&symbols)
+			.map_err(|err| PotError::pricing(&symbols.join(","), err))?;
+		let mut prices = HashMap::new();
+		for (symbol, result) in results.iter() {
+			let usd_price = match result {
+				PricingResult::Priced { usd_price, .. } => *usd_price,
+				_ => return Err(PotError::pricing(symbol, "no price quoted")),
+			};
+			if let Some(asset_tag) = assets_by_symbol.get(symbol) {
+				prices.insert(asset_tag.to_owned(), usd_price.as_f64());
+			}
+		}
+		prices
+	};
+	Ok(prices_by_asset)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use crate::asset_tag::AssetTag;
+	use crate::config::PriceProvider;
+	use crate::pot::Pot;
+	use crate::prices::{fetch_prices, fetch_prices_with, local_prices, pot_values, subpot_prices};
+	use crate::testing::{add_subpot, lot, temp_pot};
+
+	#[test]
+	fn values_same_named_subpots_by_path() {
+		let (_dir, mut root) = temp_pot("values");
+		let mut a = add_subpot(&mut root, "a", vec![]);
+		let mut b = add_subpot(&mut root, "b", vec![lot("main", "VTI", 1.0)]);
+		let a_kid = add_subpot(&mut a, "kid", vec![lot("main", "VTI", 2.0)]);
+		let b_kid = add_subpot(&mut b, "kid", vec![lot("main", "VTI", 5.0)]);
+		let prices: HashMap<AssetTag, f64> = vec![(AssetTag::from("VTI"), 100.0)].into_iter().collect();
+		let values = pot_values(&root, &prices).unwrap();
+		assert_eq!(values[a_kid.pot_path()], 200.0);
+		assert_eq!(values[b_kid.pot_path()], 500.0);
+		assert_eq!(values[a.pot_path()], 200.0);
+		assert_eq!(values[b.pot_path()], 600.0);
+		assert_eq!(values[root.pot_path()], 800.0);
+		assert_eq!(subpot_prices(&b, &prices, &values).unwrap()[&AssetTag::from(":kid")], 500.0);
+		assert_eq!(local_prices(&a, &prices).unwrap()[&AssetTag::from(":kid")], 200.0);
+		assert_eq!(local_prices(&b, &prices).unwrap()[&AssetTag::from(":kid")], 500.0);
+	}
+
+	#[test]
+	fn fetched_prices_leave_nested_subpots_to_their_parents() {
+		let (_dir, mut root) = temp_pot("fetched");
+		let mut a = add_subpot(&mut root, "a", vec![]);
+		let mut b = add_subpot(&mut root, "b", vec![]);
+		add_subpot(&mut a, "kid", vec![lot("main", "VTI", 2.0)]);
+		add_subpot(&mut b, "kid", vec![lot("main", "VTI", 5.0)]);
+		let mut config = root.read_local_config().unwrap();
+		config.prices.provider = Some(PriceProvider::Manual);
+		config.prices.quotes.insert(AssetTag::from("VTI"), 100.0);
+		root.write_local_config(&config).unwrap();
+		let prices = fetch_prices(&root).unwrap();
+		assert_eq!(prices[&AssetTag::from(":a")], 200.0);
+		assert_eq!(prices[&AssetTag::from(":b")], 500.0);
+		assert!(!prices.contains_key(&AssetTag::from(":kid")));
+		assert_eq!(a.read_market_value(&local_prices(&a, &prices).unwrap()).unwrap(), 200.0);
+		assert_eq!(b.read_market_value(&local_prices(&b, &prices).unwrap()).unwrap(), 500.0);
+	}
+
+	#[test]
+	fn converts_quotes_and_cash_into_the_base_currency() {
+		let (vti, shop, ry) = (AssetTag::from("VTI"), AssetTag::from("SHOP"), AssetTag::from("RY"));
+		let (eur, cad, usd) = (AssetTag::currency("EUR"), AssetTag::currency("CAD"), AssetTag::usd());
+		let (_dir, mut pot) = temp_pot("fx");
+		pot.write_lots(&[lot("main", "VTI", 1.0), lot("main", "SHOP", 1.0)]).unwrap();
+		pot.write_cash(&cad, 100.0).unwrap();
+		let mut config = pot.read_local_config().unwrap();
+		config.prices.provider = Some(PriceProvider::Manual);
+		config.currency = Some("EUR".to_string());
+		config.prices.quotes = vec![(vti.clone(), 220.0), (shop.clone(), 100.0), (ry.clone(), 150.0), (eur.clone(), 1.1), (cad.clone(), 0.77)]
+			.into_iter().collect();
+		config.prices.quote_currencies = vec![(shop.clone(), "CAD".to_string()), (ry.clone(), "CAD".to_string())].into_iter().collect();
+		pot.write_local_config(&config).unwrap();
+		let prices = fetch_prices_with(&pot, vec![ry.clone()].into_iter().collect()).unwrap();
+		let close = |asset: &AssetTag, expected: f64| assert!((prices[asset] - expected).abs() < 1e-9, "{:?}", asset);
+		close(&eur, 1.0);
+		close(&usd, 1.0 / 1.1);
+		close(&cad, 0.7);
+		close(&vti, 200.0);
+		close(&shop, 70.0);
+		close(&ry, 105.0);
+	}
+}
@@ -1,5 +1,5 @@
-use crate::asset_tag::AssetTag;
-use crate::lot::Lot;
+use pot::{AssetTag, Lot};
+
 use crate::print;
 
 pub fn title(s: &str) {
@@ -8,13 +8,13 @@ pub fn title(s: &str) {
 	println!("{:=<1$}", "", width);
 }
 
-pub fn targets(targets: &Vec<AssetTag>) {
+pub fn targets(targets: &[AssetTag]) {
 	let symbols = targets.iter().map(|it| it.as_str().to_string()).collect::<Vec<_>>();
 	let line: String = symbols.join(",");
 	println!("{}", line);
 }
 
-pub fn lots(lots: &Vec<Lot>) {
+pub fn lots(lots: &[Lot]) {
 	lots.iter().for_each(print::lot);
 }
 
@@ -0,0 +1,186 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+
+use crate::asset_tag::AssetTag;
+use crate::disk;
+use crate::error::PotError;
+use crate::pot::Pot;
+use crate::prices::local_prices;
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Assumption {
+	#[serde(rename = "return")]
+	pub expected_return: f64,
+	pub volatility: f64,
+}
+
+impl Assumption {
+	pub fn default_for(class: &str) -> Self {
+		let (expected_return, volatility) = match class {
+			"equity" | "fund" => (7.0, 16.0),
+			"bond" => (3.0, 6.0),
+			"realestate" => (5.0, 12.0),
+			"crypto" => (10.0, 70.0),
+			"cash" => (0.0, 0.0),
+			_ => (4.0, 10.0),
+		};
+		Assumption { expected_return, volatility }
+	}
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Assumptions {
+	pub assets: BTreeMap<AssetTag, Assumption>,
+	pub classes: BTreeMap<String, Assumption>,
+}
+
+impl Assumptions {
+	pub fn read(path: &Path) -> Result<Self, PotError> {
+		if path.exists() { disk::read_toml(path) } else { Ok(Assumptions::default()) }
+	}
+	pub fn of(&self, asset: &AssetTag) -> Assumption {
+		let class = asset.class_name();
+		self.assets.get(asset)
+			.or_else(|| self.classes.get(class))
+			.cloned()
+			.unwrap_or_else(|| Assumption::default_for(class))
+	}
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum Contribution { Monthly(f64), Yearly(f64) }
+
+impl Contribution {
+	pub fn parse(s: &str) -> Option<Self> {
+		let s = s.trim().to_lowercase();
+		let (amount, period) = match s.split_once('/') {
+			Some((amount, period)) => (amount.trim(), period.trim()),
+			None => (s.as_str(), "mo"),
+		};
+		let amount = amount.parse::<f64>().ok()?;
+		match period {
+			"mo" | "month" | "m" => Some(Contribution::Monthly(amount)),
+			"yr" | "year" | "y" => Some(Contribution::Yearly(amount)),
+			_ => None,
+		}
+	}
+	pub fn monthly(&self) -> f64 {
+		match self {
+			Contribution::Monthly(amount) => *amount,
+			Contribution::Yearly(amount) => amount / 12.0,
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Holding {
+	pub asset: AssetTag,
+	pub value: f64,
+	pub portion: f64,
+	pub assumption: Assumption,
+}
+
+pub fn holdings<P: Pot>(pot: &P, prices: &HashMap<AssetTag, f64>, assumptions: &Assumptions) -> Result<Vec<Holding>, PotError> {
+	let portions = pot.read_ladder()?.target_portions();
+	let mut values = pot.read_market_values(prices)?;
+	for asset in portions.keys() {
+		values.entry(asset.clone()).or_insert(0.0);
+	}
+	let mut holdings = Vec::new();
+	for (asset, value) in values {
+		let assumption = if asset.is_subpot() {
+			let subpot = pot.subpot(asset.as_folder_name()?);
+			blend(subpot.as_ref(), &local_prices(subpot.as_ref(), prices)?, assumptions)?
+		} else {
+			assumptions.of(&asset)
+		};
+		let portion = portions.get(&asset).cloned().unwrap_or(0.0);
+		holdings.push(Holding { asset, value, portion, assumption });
+	}
+	holdings.sort_by(|a, b| a.asset.cmp(&b.asset));
+	Ok(holdings)
+}
+
+pub fn blend<P: Pot>(pot: &P, prices: &HashMap<AssetTag, f64>, assumptions: &Assumptions) -> Result<Assumption, PotError> {
+	let holdings = holdings(pot, prices, assumptions)?;
+	let total: f64 = holdings.iter().map(|holding| holding.value).sum();
+	let weight = |holding: &Holding| if total > 0.0 { holding.value / total } else { holding.portion };
+	Ok(Assumption {
+		expected_return: holdings.iter().map(|holding| weight(holding) * holding.assumption.expected_return).sum(),
+		volatility: holdings.iter().map(|holding| weight(holding) * holding.assumption.volatility).sum(),
+	})
+}
+
+// Simulates monthly lognormal growth of each holding, with contributions split by
+// target portion, and returns the pot value at the end of every year of every path.
+pub fn simulate(holdings: &[Holding], contribution: f64, years: usize, paths: usize, seed: u64) -> Vec<Vec<f64>> {
+	let mut rng = StdRng::seed_from_u64(seed);
+	let total_portion: f64 = holdings.iter().map(|holding| holding.portion).sum();
+	let monthly = holdings.iter()
+		.map(|holding| {
+			let sigma = holding.assumption.volatility / 100.0 / 12f64.sqrt();
+			let mu = (1.0 + holding.assumption.expected_return / 100.0).ln() / 12.0 - sigma * sigma / 2.0;
+			(mu, sigma)
+		})
+		.collect::<Vec<_>>();
+	let mut outcomes = Vec::with_capacity(paths);
+	for _ in 0..paths {
+		let mut values = holdings.iter().map(|holding| holding.value).collect::<Vec<_>>();
+		let mut yearly = Vec::with_capacity(years);
+		for _ in 0..years {
+			for _ in 0..12 {
+				for (index, (mu, sigma)) in monthly.iter().enumerate() {
+					values[index] *= (mu + sigma * standard_normal(&mut rng)).exp();
+					values[index] += if total_portion > 0.0 {
+						contribution * holdings[index].portion / total_portion
+					} else {
+						contribution / holdings.len() as f64
+					};
+				}
+			}
+			yearly.push(values.iter().sum());
+		}
+		outcomes.push(yearly);
+	}
+	outcomes
+}
+
+fn standard_normal(rng: &mut StdRng) -> f64 {
+	let u1: f64 = 1.0 - rng.gen::<f64>();
+	let u2: f64 = rng.gen::<f64>();
+	(-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+pub fn percentile(sorted: &[f64], percent: f64) -> f64 {
+	if sorted.is_empty() {
+		return 0.0;
+	}
+	let rank = (percent / 100.0 * (sorted.len() - 1) as f64).round() as usize;
+	sorted[rank.min(sorted.len() - 1)]
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::asset_tag::AssetTag;
+	use crate::projection::{Assumption, Contribution, Holding, simulate};
+
+	#[test]
+	fn seeded_simulations_repeat() {
+		let holdings = [Holding {
+			asset: AssetTag::equity("vti"),
+			value: 1000.0,
+			portion: 1.0,
+			assumption: Assumption { expected_return: 7.0, volatility: 15.0 },
+		}];
+		let contribution = Contribution::parse("1200/yr").unwrap().monthly();
+		assert_eq!(simulate(&holdings, contribution, 5, 20, 42), simulate(&holdings, contribution, 5, 20, 42));
+		assert_ne!(simulate(&holdings, contribution, 5, 20, 42), simulate(&holdings, contribution, 5, 20, 43));
+		let riskless = [Holding { assumption: Assumption { expected_return: 0.0, volatility: 0.0 }, ..holdings[0].clone() }];
+		assert_eq!(simulate(&riskless, contribution, 2, 1, 7)[0], vec![2200.0, 3400.0]);
+	}
+}
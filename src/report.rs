@@ -0,0 +1,837 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{Datelike, NaiveDate};
+
+use crate::action::CorporateAction;
+use crate::asset_tag::AssetTag;
+use crate::config::{AccountType, GoalConfig, PotConfig};
+use crate::core::PotPath;
+use crate::date;
+use crate::error::PotError;
+use crate::income::{self, Income};
+use crate::lot::Lot;
+use crate::metadata::{default_category, TaxEfficiency};
+use crate::pot::Pot;
+use crate::prices::local_prices;
+use crate::projection::{self, Assumptions};
+use crate::sale::Sale;
+use crate::snapshot::{self, PeriodReturn, Snapshot};
+use crate::wash::{self, Purchase, WashSale};
+
+#[derive(Clone, Debug)]
+pub struct Status {
+	pub free_cash: f64,
+	pub full_value: f64,
+	pub rows: Vec<StatusRow>,
+}
+
+#[derive(Clone, Debug)]
+pub struct StatusRow {
+	pub asset: AssetTag,
+	pub group: Option<AssetTag>,
+	pub shares: f64,
+	pub market_value: f64,
+	pub market_portion: f64,
+	pub target_portion: f64,
+	pub target_value: f64,
+	pub drift: f64,
+	pub in_band: bool,
+}
+
+impl StatusRow {
+	pub fn action(&self) -> f64 { -self.drift }
+}
+
+impl Status {
+	// Group rows only sum their members and cash is what trades settle in, so neither is traded.
+	pub fn offenders(&self) -> Vec<&StatusRow> {
+		self.rows.iter()
+			.filter(|row| !row.in_band && !row.asset.is_group() && !row.asset.is_currency())
+			.collect()
+	}
+}
+
+pub fn status(pot: &impl Pot, prices: &HashMap<AssetTag, f64>) -> Result<Status, PotError> {
+	let ladder = pot.read_ladder()?;
+	let portfolio = pot.read_portfolio()?;
+	let config = pot.read_config()?;
+	let base_currency = config.base_currency();
+	let off_target_symbols = {
+		let mut set = portfolio.symbols().difference(&ladder.target_symbols()).cloned().collect::<HashSet<_>>();
+		set.insert(base_currency);
+		set
+	};
+	let portion_targets = {
+		let mut portion_targets = ladder.target_portions();
+		for off_target_symbol in &off_target_symbols {
+			portion_targets.insert(off_target_symbol.clone(), 0.0);
+		}
+		portion_targets
+	};
+	let lot_counts = portfolio.share_counts();
+	let mut market_values = portfolio.market_values(prices)?;
+	for ref target_symbol in ladder.target_symbols() {
+		if !market_values.contains_key(target_symbol) {
+			market_values.insert(target_symbol.clone(), 0.0);
+		}
+	}
+	let full_value: f64 = market_values.values().sum();
+	let group_portions = ladder.group_portions();
+	let ordered_symbols = {
+		let mut symbols = Vec::new();
+		for (symbol, group) in ladder.rows_descending() {
+			if !symbols.iter().any(|(it, _)| it == &symbol) {
+				symbols.push((symbol, group));
+			}
+		}
+		let mut ordered_off_target_symbols = off_target_symbols.iter().cloned().collect::<Vec<_>>();
+		ordered_off_target_symbols.sort();
+		symbols.extend(ordered_off_target_symbols.into_iter().map(|symbol| (symbol, None)));
+		symbols
+	};
+	let rows = ordered_symbols.into_iter().map(|(symbol, group)| {
+		let (target_portion, shares, market_value) = if symbol.is_group() {
+			let market_value = ladder.group_members(&symbol).iter()
+				.map(|member| market_values.get(member).cloned().unwrap_or(0.0))
+				.sum();
+			(group_portions.get(&symbol).cloned().unwrap_or(0.0), 0.0, market_value)
+		} else {
+			(
+				portion_targets.get(&symbol).cloned().unwrap_or(0.0),
+				lot_counts.get(&symbol).cloned().unwrap_or(0.0),
+				market_values.get(&symbol).cloned().unwrap_or(0.0),
+			)
+		};
+		let target_value = target_portion * full_value;
+		let drift = market_value - target_value;
+		StatusRow {
+			market_portion: market_value / full_value,
+			in_band: config.rebalance.band(&symbol).contains(drift, target_value),
+			drift,
+			asset: symbol,
+			group,
+			shares,
+			market_value,
+			target_portion,
+			target_value,
+		}
+	}).collect::<Vec<_>>();
+	let free_cash = portfolio.cash_value(prices)?;
+	Ok(Status { free_cash, full_value, rows })
+}
+
+pub fn market_values(pot: &impl Pot, prices: &HashMap<AssetTag, f64>) -> Result<Vec<(AssetTag, f64)>, PotError> {
+	let mut pairs = pot.read_market_values(prices)?.into_iter().collect::<Vec<_>>();
+	pairs.sort_by_key(|x| x.0.to_owned());
+	Ok(pairs)
+}
+
+pub fn asset_values<P: Pot>(pot: &P, prices: &HashMap<AssetTag, f64>) -> Result<Vec<(AssetTag, f64)>, PotError> {
+	let mut values: BTreeMap<AssetTag, f64> = BTreeMap::new();
+	for (_, pot) in tagged_pots(pot)? {
+		for (asset, value) in pot.read_market_values(&local_prices(&pot, prices)?)? {
+			if !asset.is_subpot() && !asset.is_currency() {
+				*values.entry(asset).or_insert(0.0) += value;
+			}
+		}
+	}
+	Ok(values.into_iter().collect())
+}
+
+// An asset takes its category from the first pot that names one, checking the root
+// and then subpots in path order, so every holding of it lands in the same bucket.
+pub fn allocation<P: Pot>(pot: &P, prices: &HashMap<AssetTag, f64>, key: &str) -> Result<Vec<(String, f64)>, PotError> {
+	let key = key.to_lowercase();
+	let mut pots = tagged_pots(pot)?;
+	pots[1..].sort_by(|a, b| a.0.cmp(&b.0));
+	let mut metadata = Vec::new();
+	for (_, pot) in &pots {
+		metadata.push(pot.read_asset_metadata()?);
+	}
+	let category = |asset: &AssetTag| -> String {
+		metadata.iter()
+			.find_map(|metadata| metadata.category(asset, &key))
+			.map(str::to_string)
+			.unwrap_or_else(|| default_category(asset, &key))
+	};
+	let mut values: BTreeMap<String, f64> = BTreeMap::new();
+	for (_, pot) in &pots {
+		for lot in pot.read_lots()?.into_iter().filter(|lot| !lot.asset_tag.is_subpot()) {
+			let price = prices.get(&lot.asset_tag)
+				.ok_or_else(|| PotError::pricing(lot.asset_tag.as_str(), "no price available"))?;
+			*values.entry(category(&lot.asset_tag)).or_insert(0.0) += lot.share_count.as_f64() * price;
+		}
+		for (currency, amount) in pot.read_cash_balances()? {
+			let price = prices.get(&currency)
+				.ok_or_else(|| PotError::pricing(currency.as_str(), "no exchange rate available"))?;
+			*values.entry(category(&currency)).or_insert(0.0) += amount * price;
+		}
+	}
+	let mut values = values.into_iter().collect::<Vec<_>>();
+	values.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+	Ok(values)
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Grouping { AccountType, Custodian, Owner }
+
+impl Grouping {
+	pub fn parse(s: &str) -> Option<Self> {
+		match s.trim().to_lowercase().as_str() {
+			"type" => Some(Grouping::AccountType),
+			"custodian" => Some(Grouping::Custodian),
+			"owner" => Some(Grouping::Owner),
+			_ => None,
+		}
+	}
+	fn name(&self, config: &PotConfig, custodian: &str) -> String {
+		let registered = config.custodians.get(custodian);
+		match self {
+			Grouping::AccountType => registered.map(|it| it.account_type().as_str()).unwrap_or("unregistered").to_string(),
+			Grouping::Custodian => custodian.to_string(),
+			Grouping::Owner => registered.and_then(|it| it.owner.as_deref()).unwrap_or("unassigned").to_string(),
+		}
+	}
+}
+
+pub fn values_by<P: Pot>(pot: &P, prices: &HashMap<AssetTag, f64>, grouping: Grouping) -> Result<Vec<(String, f64)>, PotError> {
+	let mut pots = vec![pot.clone()];
+	pots.extend(pot.read_deep_subpots()?.into_iter().map(|(_, subpot)| *subpot));
+	let mut values: BTreeMap<String, f64> = BTreeMap::new();
+	let mut cash = 0.0;
+	for pot in pots {
+		let config = pot.read_config()?;
+		let portfolio = pot.read_portfolio()?;
+		let prices = local_prices(&pot, prices)?;
+		for (lot, value) in portfolio.lot_values(&prices)? {
+			if !lot.asset_tag.is_subpot() {
+				*values.entry(grouping.name(&config, lot.custodian.as_str())).or_insert(0.0) += value;
+			}
+		}
+		cash += portfolio.cash_value(&prices)?;
+	}
+	if cash != 0.0 {
+		*values.entry("cash".to_string()).or_insert(0.0) += cash;
+	}
+	Ok(values.into_iter().collect())
+}
+
+#[derive(Clone, Debug)]
+pub struct Placement {
+	pub pot: AssetTag,
+	pub asset: AssetTag,
+	pub account_type: AccountType,
+	pub suggested: f64,
+	pub held: f64,
+}
+
+// Places each pot's targets in the account types its own lots are held in. Subpot
+// targets are placed by the subpot, so only the remaining targets share the capacity.
+pub fn location<P: Pot>(pot: &P, prices: &HashMap<AssetTag, f64>) -> Result<Vec<Placement>, PotError> {
+	let mut placements = Vec::new();
+	for (tag, pot) in tagged_pots(pot)? {
+		placements.extend(local_location(&tag, &pot, prices)?);
+	}
+	Ok(placements)
+}
+
+fn local_location<P: Pot>(tag: &AssetTag, pot: &P, prices: &HashMap<AssetTag, f64>) -> Result<Vec<Placement>, PotError> {
+	let config = pot.read_config()?;
+	let metadata = pot.read_asset_metadata()?;
+	let mut capacities: BTreeMap<AccountType, f64> = BTreeMap::new();
+	let mut held: HashMap<(AssetTag, AccountType), f64> = HashMap::new();
+	for lot in pot.read_lots()?.into_iter().filter(|lot| !lot.asset_tag.is_subpot()) {
+		if let Some(account_type) = config.account_type(lot.custodian.as_str()) {
+			let price = prices.get(&lot.asset_tag)
+				.ok_or_else(|| PotError::pricing(lot.asset_tag.as_str(), "no price available"))?;
+			let value = lot.share_count.as_f64() * price;
+			*capacities.entry(account_type).or_insert(0.0) += value;
+			*held.entry((lot.asset_tag, account_type)).or_insert(0.0) += value;
+		}
+	}
+	let total: f64 = capacities.values().sum();
+	let mut targets = pot.read_ladder()?.target_portions().into_iter()
+		.filter(|(asset, _)| !asset.is_subpot())
+		.collect::<Vec<_>>();
+	let portions: f64 = targets.iter().map(|(_, portion)| portion).sum();
+	targets.sort_by(|(a, a_portion), (b, b_portion)| {
+		TaxEfficiency::of(a, &metadata).cmp(&TaxEfficiency::of(b, &metadata))
+			.then(b_portion.partial_cmp(a_portion).unwrap_or(std::cmp::Ordering::Equal))
+			.then(a.cmp(b))
+	});
+	let mut placements = Vec::new();
+	for (asset, portion) in targets {
+		let mut remaining = if portions > 0.0 { portion / portions * total } else { 0.0 };
+		for account_type in TaxEfficiency::of(&asset, &metadata).preferred_accounts().iter() {
+			if let Some(capacity) = capacities.get_mut(account_type) {
+				let suggested = remaining.min(*capacity);
+				if suggested > 0.0 {
+					*capacity -= suggested;
+					remaining -= suggested;
+					let held = held.get(&(asset.clone(), *account_type)).cloned().unwrap_or(0.0);
+					placements.push(Placement { pot: tag.clone(), asset: asset.clone(), account_type: *account_type, suggested, held });
+				}
+			}
+		}
+	}
+	Ok(placements)
+}
+
+#[derive(Clone, Debug)]
+pub struct Realized {
+	pub sale: Sale,
+	pub disallowed: f64,
+	pub basis_adjustment: f64,
+}
+
+impl Realized {
+	pub fn basis(&self) -> Option<f64> { self.sale.basis.map(|basis| basis + self.basis_adjustment) }
+	pub fn gain(&self) -> Option<f64> { self.basis().map(|basis| self.sale.proceeds - basis + self.disallowed) }
+	pub fn is_wash_sale(&self) -> bool { self.disallowed > 0.0 }
+}
+
+pub fn realized_sales<P: Pot>(pot: &P, year: i32, taxable_only: bool) -> Result<Vec<Realized>, PotError> {
+	let washes = wash_sales(pot)?;
+	let mut pots = vec![pot.clone()];
+	pots.extend(pot.read_deep_subpots()?.into_iter().map(|(_, subpot)| *subpot));
+	let mut sales = Vec::new();
+	for pot in pots {
+		let config = pot.read_config()?;
+		let account_type = |sale: &Sale| config.account_type(sale.custodian.as_str()).unwrap_or(AccountType::Taxable);
+		sales.extend(pot.read_records::<Sale>()?.into_iter()
+			.filter(|sale| sale.sold.year() == year)
+			.filter(|sale| !taxable_only || account_type(sale) == AccountType::Taxable));
+	}
+	sales.sort_by(|a, b| a.sold.cmp(&b.sold).then(a.acquired.cmp(&b.acquired)).then(a.asset_tag.cmp(&b.asset_tag)));
+	let realized = sales.into_iter().map(|sale| {
+		let disallowed = washes.iter()
+			.filter(|wash| wash.sale.uid == sale.uid && wash.sale.sold == sale.sold)
+			.map(|wash| wash.disallowed)
+			.sum();
+		let basis_adjustment = washes.iter()
+			.filter(|wash| wash.replacement_uid == sale.uid)
+			.map(|wash| wash.disallowed * (sale.count / wash.replacement_count).min(1.0))
+			.sum();
+		Realized { sale, disallowed, basis_adjustment }
+	}).collect();
+	Ok(realized)
+}
+
+pub fn trades<P: Pot>(pot: &P) -> Result<(Vec<Lot>, Vec<Sale>), PotError> {
+	let mut lots = pot.read_lots()?;
+	let mut sales = pot.read_records::<Sale>()?;
+	for (_, subpot) in pot.read_deep_subpots()? {
+		lots.extend(subpot.read_lots()?);
+		sales.extend(subpot.read_records::<Sale>()?);
+	}
+	lots.retain(|lot| !lot.asset_tag.is_subpot());
+	Ok((lots, sales))
+}
+
+pub fn wash_sales<P: Pot>(pot: &P) -> Result<Vec<WashSale>, PotError> {
+	let (lots, sales) = trades(pot)?;
+	Ok(wash::detect(&sales, &Purchase::from_lots_and_sales(&lots, &sales)))
+}
+
+#[derive(Clone, Debug)]
+pub struct Trade {
+	pub asset: AssetTag,
+	pub value: f64,
+	pub shares: f64,
+	pub wash_risk: Option<NaiveDate>,
+	pub unchecked: Vec<u64>,
+}
+
+impl Trade {
+	pub fn is_buy(&self) -> bool { self.value > 0.0 }
+}
+
+pub fn rebalance<P: Pot>(pot: &P, prices: &HashMap<AssetTag, f64>, date: NaiveDate) -> Result<Vec<Trade>, PotError> {
+	let (lots, sales) = trades(pot)?;
+	let purchases = Purchase::from_lots_and_sales(&lots, &sales);
+	let mut trades = Vec::new();
+	for row in status(pot, prices)?.offenders() {
+		let value = row.action();
+		let price = prices.get(&row.asset).cloned().unwrap_or(0.0);
+		let shares = if row.asset.is_subpot() || price == 0.0 { 0.0 } else { row.asset.round_shares(value / price) };
+		let at_loss = value < 0.0 && lots.iter()
+			.filter(|lot| lot.asset_tag == row.asset)
+			.any(|lot| lot.basis_per_share().iter().any(|basis| *basis > price));
+		let wash_risk = if value > 0.0 {
+			wash::recent_loss(&sales, &row.asset, date).map(|sale| sale.sold)
+		} else if at_loss {
+			wash::recent_purchase(&purchases, &row.asset, date).map(|purchase| purchase.acquired)
+		} else {
+			None
+		};
+		let unchecked = if at_loss { wash::undated_lots(&lots, &row.asset) } else { Vec::new() };
+		trades.push(Trade { asset: row.asset.clone(), value, shares, wash_risk, unchecked });
+	}
+	Ok(trades)
+}
+
+#[derive(Clone, Debug)]
+pub struct Harvest {
+	pub pot: AssetTag,
+	pub lot: Lot,
+	pub value: f64,
+	pub loss: f64,
+	pub drift: f64,
+	pub drift_after: f64,
+	pub substitutes: Vec<(AssetTag, f64)>,
+	pub wash_risk: Option<NaiveDate>,
+	pub unchecked: Vec<u64>,
+}
+
+// Drift is measured against the targets of the pot holding the lot, and wash-sale
+// risk against purchases anywhere in the tree.
+pub fn harvest<P: Pot>(pot: &P, prices: &HashMap<AssetTag, f64>, threshold: f64, date: NaiveDate) -> Result<Vec<Harvest>, PotError> {
+	let (deep_lots, sales) = trades(pot)?;
+	let purchases = Purchase::from_lots_and_sales(&deep_lots, &sales);
+	let mut harvests = Vec::new();
+	for (tag, pot) in tagged_pots(pot)? {
+		harvests.extend(local_harvest(&tag, &pot, &local_prices(&pot, prices)?, threshold, date, &deep_lots, &purchases)?);
+	}
+	harvests.sort_by(|a, b| b.loss.partial_cmp(&a.loss).unwrap_or(std::cmp::Ordering::Equal));
+	Ok(harvests)
+}
+
+fn local_harvest<P: Pot>(
+	tag: &AssetTag, pot: &P, prices: &HashMap<AssetTag, f64>, threshold: f64, date: NaiveDate,
+	deep_lots: &[Lot], purchases: &[Purchase],
+) -> Result<Vec<Harvest>, PotError> {
+	let config = pot.read_config()?;
+	let drifts = status(pot, prices)?.rows.into_iter()
+		.map(|row| (row.asset, row.drift))
+		.collect::<HashMap<_, _>>();
+	let drift = |asset: &AssetTag| drifts.get(asset).cloned().unwrap_or(0.0);
+	let mut harvests = Vec::new();
+	for (lot, value) in pot.read_portfolio()?.lot_values(prices)? {
+		let account_type = config.account_type(lot.custodian.as_str()).unwrap_or(AccountType::Taxable);
+		let loss = match lot.basis {
+			Some(basis) if account_type == AccountType::Taxable => basis - value,
+			_ => continue,
+		};
+		if loss <= threshold.max(0.0) {
+			continue;
+		}
+		let substitutes = config.substitutes.get(&lot.asset_tag).cloned().unwrap_or_default()
+			.into_iter()
+			.map(|substitute| {
+				let drift_after = drift(&substitute) + value;
+				(substitute, drift_after)
+			})
+			.collect();
+		let wash_risk = purchases.iter()
+			.filter(|purchase| purchase.uid != lot.uid)
+			.filter(|purchase| purchase.asset_tag == lot.asset_tag && purchase.acquired <= date)
+			.filter(|purchase| wash::in_window(purchase.acquired, date))
+			.map(|purchase| purchase.acquired)
+			.max();
+		let mut unchecked = wash::undated_lots(deep_lots, &lot.asset_tag);
+		unchecked.retain(|uid| *uid != lot.uid);
+		harvests.push(Harvest {
+			pot: tag.clone(),
+			lot: lot.clone(),
+			value,
+			loss,
+			drift: drift(&lot.asset_tag),
+			drift_after: drift(&lot.asset_tag) - value,
+			substitutes,
+			wash_risk,
+			unchecked,
+		});
+	}
+	Ok(harvests)
+}
+
+pub fn deep_income<P: Pot>(pot: &P) -> Result<Vec<Income>, PotError> {
+	let mut records = pot.read_records::<Income>()?;
+	for (_, subpot) in pot.read_deep_subpots()? {
+		records.extend(subpot.read_records::<Income>()?);
+	}
+	records.sort_by_key(|income| income.date);
+	Ok(records)
+}
+
+#[derive(Clone, Debug)]
+pub struct Projection {
+	pub asset: AssetTag,
+	pub market_value: f64,
+	pub annual_income: f64,
+	pub manual_yield: bool,
+}
+
+impl Projection {
+	pub fn yield_portion(&self) -> f64 {
+		if self.market_value > 0.0 { self.annual_income / self.market_value } else { 0.0 }
+	}
+}
+
+pub fn projected_income<P: Pot>(pot: &P, prices: &HashMap<AssetTag, f64>, date: NaiveDate) -> Result<Vec<Projection>, PotError> {
+	let config = pot.read_config()?;
+	let records = deep_income(pot)?;
+	let mut values: BTreeMap<AssetTag, f64> = BTreeMap::new();
+	for (_, pot) in tagged_pots(pot)? {
+		for lot in pot.read_lots()?.into_iter().filter(|lot| !lot.asset_tag.is_subpot() && !lot.asset_tag.is_currency()) {
+			let price = prices.get(&lot.asset_tag)
+				.ok_or_else(|| PotError::pricing(lot.asset_tag.as_str(), "no price available"))?;
+			*values.entry(lot.asset_tag).or_insert(0.0) += lot.share_count.as_f64() * price;
+		}
+	}
+	let mut projections = Vec::new();
+	for (asset, market_value) in values {
+		let projection = match config.yields.get(&asset) {
+			Some(percent) => Projection { annual_income: market_value * percent / 100.0, manual_yield: true, asset, market_value },
+			None => Projection { annual_income: income::trailing_income(&records, &asset, date), manual_yield: false, asset, market_value },
+		};
+		projections.push(projection);
+	}
+	Ok(projections)
+}
+
+#[derive(Clone, Debug)]
+pub struct GoalStatus {
+	pub pot: AssetTag,
+	pub goal: GoalConfig,
+	pub value: f64,
+	pub months: i64,
+	pub expected_return: f64,
+	pub required_monthly: f64,
+	pub on_track: bool,
+}
+
+impl GoalStatus {
+	pub fn funded(&self) -> f64 { if self.goal.amount > 0.0 { self.value / self.goal.amount } else { 1.0 } }
+}
+
+pub fn goals<P: Pot>(pot: &P, prices: &HashMap<AssetTag, f64>, assumptions: &Assumptions, date: NaiveDate) -> Result<Vec<GoalStatus>, PotError> {
+	let mut goals = Vec::new();
+	for (tag, pot) in tagged_pots(pot)? {
+		let goal = match pot.read_local_config()?.goal.0 {
+			Some(goal) => goal,
+			None => continue,
+		};
+		let prices = local_prices(&pot, prices)?;
+		let value = pot.read_market_value(&prices)?;
+		let expected_return = projection::blend(&pot, &prices, assumptions)?.expected_return;
+		let months = date::months_between(date, goal.date).max(0);
+		let rate = (1.0 + expected_return / 100.0).powf(1.0 / 12.0) - 1.0;
+		let required_monthly = required_monthly(value, goal.amount, months, rate);
+		let on_track = required_monthly <= goal.monthly.unwrap_or(0.0) + 0.005;
+		goals.push(GoalStatus { pot: tag, goal, value, months, expected_return, required_monthly, on_track });
+	}
+	Ok(goals)
+}
+
+// The monthly contribution that grows value to amount over the months at the monthly rate.
+fn required_monthly(value: f64, amount: f64, months: i64, rate: f64) -> f64 {
+	let growth = (1.0 + rate).powi(months as i32);
+	let shortfall = amount - value * growth;
+	if shortfall <= 0.0 {
+		0.0
+	} else if months == 0 {
+		shortfall
+	} else if rate.abs() < 1e-12 {
+		shortfall / months as f64
+	} else {
+		shortfall * rate / (growth - 1.0)
+	}
+}
+
+fn tagged_pots<P: Pot>(pot: &P) -> Result<Vec<(AssetTag, P)>, PotError> {
+	let mut pots = vec![(AssetTag::from(":"), pot.clone())];
+	pots.extend(pot.read_deep_subpots()?.into_iter().map(|(tag, subpot)| (tag, *subpot)));
+	Ok(pots)
+}
+
+// Each pot's benchmark is its configured weights, or its ladder targets at target weight.
+pub fn benchmark_weights<P: Pot>(pot: &P) -> Result<HashMap<PotPath, HashMap<AssetTag, f64>>, PotError> {
+	let mut weights = HashMap::new();
+	for (_, pot) in tagged_pots(pot)? {
+		let benchmark = pot.read_local_config()?.benchmark.0;
+		let portions = if benchmark.is_empty() {
+			pot.read_ladder()?.target_portions()
+		} else {
+			benchmark.into_iter().collect()
+		};
+		weights.insert(pot.pot_path().clone(), portions);
+	}
+	Ok(weights)
+}
+
+pub fn snapshot<P: Pot>(pot: &P, prices: &HashMap<AssetTag, f64>, date: NaiveDate) -> Result<Vec<Snapshot>, PotError> {
+	let weights = benchmark_weights(pot)?;
+	let mut snapshots = Vec::new();
+	for (_, pot) in tagged_pots(pot)? {
+		let mut shares = pot.read_portfolio()?.share_counts().into_iter().collect::<BTreeMap<_, _>>();
+		for asset in weights.get(pot.pot_path()).into_iter().flat_map(HashMap::keys) {
+			shares.entry(asset.clone()).or_insert(0.0);
+		}
+		let prices = local_prices(&pot, prices)?;
+		for (symbol, shares) in shares {
+			if let Some(price) = prices.get(&symbol) {
+				snapshots.push(Snapshot { date, pot: pot.pot_path().clone(), symbol, shares, price: *price });
+			}
+		}
+	}
+	Ok(snapshots)
+}
+
+pub fn benchmark<P: Pot>(pot: &P) -> Result<Vec<PeriodReturn>, PotError> {
+	Ok(snapshot::compare(&pot.read_records::<Snapshot>()?, &pot.read_records::<CorporateAction>()?, &benchmark_weights(pot)?, pot.pot_path()))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use chrono::NaiveDate;
+
+	use crate::Custodian;
+	use crate::asset_tag::AssetTag;
+	use crate::config::{AccountType, Band, CustodianConfig, GoalConfig};
+	use crate::core::Ramp;
+	use crate::income::Income;
+	use crate::lot::Lot;
+	use crate::pot::Pot;
+	use crate::projection::{Assumption, Assumptions};
+	use crate::report::{
+		allocation, asset_values, goals, Grouping, harvest, location, market_values, projected_income, Realized, realized_sales,
+		required_monthly, status, values_by,
+	};
+	use crate::sale::Sale;
+	use crate::testing::{add_subpot, lot, temp_pot};
+
+	fn sale(custodian: &str, symbol: &str, sold: NaiveDate, proceeds: f64, basis: f64) -> Sale {
+		Sale {
+			uid: Lot::random_uid(),
+			custodian: Custodian(custodian.to_string()),
+			asset_tag: AssetTag::from(symbol),
+			count: 1.0,
+			acquired: NaiveDate::from_ymd_opt(2020, 1, 2),
+			sold,
+			proceeds,
+			basis: Some(basis),
+		}
+	}
+
+	#[test]
+	fn allocation_prefers_root_categories_across_the_tree() {
+		let (_dir, mut pot) = temp_pot("allocation");
+		pot.write_lots(&[lot("main", "VTI", 1.0)]).unwrap();
+		let kid = add_subpot(&mut pot, "kid", vec![lot("main", "VTI", 2.0), lot("main", "BND", 4.0)]);
+		let mut metadata = pot.read_asset_metadata().unwrap();
+		metadata.set_category(&AssetTag::from("VTI"), "region", "us");
+		pot.write_asset_metadata(&metadata).unwrap();
+		let mut metadata = kid.read_asset_metadata().unwrap();
+		metadata.set_category(&AssetTag::from("VTI"), "region", "intl");
+		metadata.set_category(&AssetTag::from("BND"), "region", "bonds");
+		kid.write_asset_metadata(&metadata).unwrap();
+		let prices: HashMap<AssetTag, f64> = vec![(AssetTag::from("VTI"), 100.0), (AssetTag::from("BND"), 50.0)].into_iter().collect();
+		let values = allocation(&pot, &prices, "Region").unwrap();
+		assert_eq!(values, vec![("us".to_string(), 300.0), ("bonds".to_string(), 200.0)]);
+	}
+
+	#[test]
+	fn projects_income_from_yields_and_trailing_records_across_the_tree() {
+		let (_dir, mut pot) = temp_pot("income");
+		pot.write_lots(&[lot("main", "VTI", 1.0)]).unwrap();
+		let kid = add_subpot(&mut pot, "kid", vec![lot("main", "VTI", 1.0), lot("main", "BND", 4.0)]);
+		let mut config = pot.read_local_config().unwrap();
+		config.yields.insert(AssetTag::from("VTI"), 2.0);
+		pot.write_local_config(&config).unwrap();
+		let income = |month, amount| Income {
+			date: NaiveDate::from_ymd_opt(2024, month, 1).unwrap(),
+			custodian: Custodian("main".to_string()),
+			asset_tag: AssetTag::from("BND"),
+			amount,
+			reinvested: None,
+		};
+		kid.write_records(&[income(1, 5.0), income(6, 3.0), income(12, 2.0)]).unwrap();
+		let prices: HashMap<AssetTag, f64> = vec![(AssetTag::from("VTI"), 100.0), (AssetTag::from("BND"), 50.0)].into_iter().collect();
+		let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+		let projections = projected_income(&pot, &prices, date).unwrap();
+		assert_eq!(projections.len(), 2);
+		let (bnd, vti) = (&projections[0], &projections[1]);
+		assert_eq!((bnd.asset.as_str(), bnd.market_value, bnd.annual_income, bnd.manual_yield), ("BND", 200.0, 5.0, false));
+		assert_eq!((vti.asset.as_str(), vti.market_value, vti.annual_income, vti.manual_yield), ("VTI", 200.0, 4.0, true));
+	}
+
+	#[test]
+	fn requires_contributions_that_close_the_goal_shortfall() {
+		assert_eq!(required_monthly(1000.0, 800.0, 12, 0.01), 0.0);
+		assert_eq!(required_monthly(1000.0, 1500.0, 0, 0.01), 500.0);
+		assert!((required_monthly(1000.0, 2200.0, 12, 0.0) - 100.0).abs() < 1e-9);
+		let rate: f64 = 0.005;
+		let future_value = 100.0 * ((1.0 + rate).powi(24) - 1.0) / rate;
+		assert!((required_monthly(0.0, future_value, 24, rate) - 100.0).abs() < 1e-9);
+	}
+
+	#[test]
+	fn reports_goal_funding_for_subpots() {
+		let (_dir, mut pot) = temp_pot("goals");
+		let kid = add_subpot(&mut pot, "kid", vec![lot("main", "BND", 20.0)]);
+		let mut config = kid.read_local_config().unwrap();
+		*config.goal = Some(GoalConfig { amount: 2200.0, date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(), monthly: Some(100.0) });
+		kid.write_local_config(&config).unwrap();
+		let mut assumptions = Assumptions::default();
+		assumptions.assets.insert(AssetTag::from("BND"), Assumption { expected_return: 0.0, volatility: 0.0 });
+		let prices: HashMap<AssetTag, f64> = vec![(AssetTag::from("BND"), 50.0)].into_iter().collect();
+		let statuses = goals(&pot, &prices, &assumptions, NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()).unwrap();
+		assert_eq!(statuses.len(), 1);
+		let status = &statuses[0];
+		assert_eq!((status.pot.as_str(), status.value, status.months), (":kid", 1000.0, 12));
+		assert!((status.funded() - 1000.0 / 2200.0).abs() < 1e-9);
+		assert!((status.required_monthly - 100.0).abs() < 1e-9);
+		assert!(status.on_track);
+	}
+
+	#[test]
+	fn locates_targets_within_each_pot_and_skips_subpot_targets() {
+		let (vti, bnd, kid_tag) = (AssetTag::from("VTI"), AssetTag::from("bond:BND"), AssetTag::from(":kid"));
+		let (_dir, mut pot) = temp_pot("location");
+		pot.write_lots(&[lot("brokerage", "VTI", 1.0)]).unwrap();
+		pot.write_targets(&[vti.clone(), kid_tag.clone()]).unwrap();
+		let kid = add_subpot(&mut pot, "kid", vec![lot("ira", "bond:BND", 4.0), lot("brokerage", "VTI", 2.0)]);
+		kid.write_targets(&[bnd.clone(), vti.clone()]).unwrap();
+		let mut config = pot.read_local_config().unwrap();
+		let custodian = |account_type| CustodianConfig { account_type: Some(account_type), ..CustodianConfig::default() };
+		config.custodians.insert("brokerage".to_string(), custodian(AccountType::Taxable));
+		config.custodians.insert("ira".to_string(), custodian(AccountType::TaxDeferred));
+		pot.write_local_config(&config).unwrap();
+		let prices: HashMap<AssetTag, f64> = vec![(vti.clone(), 100.0), (bnd.clone(), 50.0), (kid_tag.clone(), 400.0)].into_iter().collect();
+		let placements = location(&pot, &prices).unwrap();
+		assert!(placements.iter().all(|placement| !placement.asset.is_subpot()));
+		let suggested = |pot: &AssetTag| placements.iter().filter(|it| &it.pot == pot).map(|it| it.suggested).sum::<f64>();
+		assert!((suggested(&AssetTag::from(":")) - 100.0).abs() < 1e-9);
+		assert!((suggested(&kid_tag) - 400.0).abs() < 1e-9);
+		let bond = placements.iter().find(|it| it.pot == kid_tag && it.asset == bnd).unwrap();
+		assert_eq!((bond.account_type, bond.held), (AccountType::TaxDeferred, 200.0));
+	}
+
+	#[test]
+	fn harvests_losses_in_subpots_with_tree_wide_wash_checks() {
+		let (vti, kid_tag) = (AssetTag::from("VTI"), AssetTag::from(":kid"));
+		let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+		let (_dir, mut pot) = temp_pot("harvest");
+		let mut loser = lot("brokerage", "VTI", 2.0);
+		loser.basis = Some(300.0);
+		loser.acquired = Some(NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+		let mut recent = lot("brokerage", "VTI", 1.0);
+		recent.acquired = Some(NaiveDate::from_ymd_opt(2025, 2, 20).unwrap());
+		let undated = lot("ira", "VTI", 1.0);
+		pot.write_lots(&[recent, undated.clone()]).unwrap();
+		let kid = add_subpot(&mut pot, "kid", vec![loser.clone()]);
+		let mut config = kid.read_local_config().unwrap();
+		config.substitutes.insert(vti.clone(), vec![AssetTag::from("ITOT")]);
+		kid.write_local_config(&config).unwrap();
+		let prices: HashMap<AssetTag, f64> = vec![(vti.clone(), 100.0), (kid_tag.clone(), 200.0)].into_iter().collect();
+		let harvests = harvest(&pot, &prices, 50.0, date).unwrap();
+		assert_eq!(harvests.len(), 1);
+		let harvest = &harvests[0];
+		assert_eq!((&harvest.pot, harvest.lot.uid, harvest.value, harvest.loss), (&kid_tag, loser.uid, 200.0, 100.0));
+		assert_eq!(harvest.substitutes.len(), 1);
+		assert_eq!(harvest.wash_risk, NaiveDate::from_ymd_opt(2025, 2, 20));
+		assert_eq!(harvest.unchecked, vec![undated.uid]);
+	}
+
+	#[test]
+	fn status_flags_drift_outside_bands() {
+		let (vti, bnd) = (AssetTag::from("VTI"), AssetTag::from("BND"));
+		let (_dir, mut pot) = temp_pot("status");
+		pot.write_lots(&[lot("main", "VTI", 6.0), lot("main", "BND", 8.0)]).unwrap();
+		pot.write_targets(&[vti.clone(), bnd.clone()]).unwrap();
+		pot.write_ramp(Ramp::Flat).unwrap();
+		let mut config = pot.read_local_config().unwrap();
+		config.rebalance.relative = Some(15.0);
+		config.rebalance.bands.insert(vti.clone(), Band { absolute: None, relative: Some(25.0) });
+		pot.write_local_config(&config).unwrap();
+		let prices: HashMap<AssetTag, f64> = vec![(vti.clone(), 100.0), (bnd.clone(), 50.0), (AssetTag::usd(), 1.0)].into_iter().collect();
+		let status = status(&pot, &prices).unwrap();
+		let row = |asset: &AssetTag| status.rows.iter().find(|row| &row.asset == asset).unwrap();
+		assert!((row(&vti).drift - 100.0).abs() < 1e-9);
+		assert!((row(&bnd).drift + 100.0).abs() < 1e-9);
+		assert!((row(&bnd).action() - 100.0).abs() < 1e-9);
+		let offenders = status.offenders().into_iter().map(|row| row.asset.clone()).collect::<Vec<_>>();
+		assert_eq!(offenders, vec![bnd]);
+	}
+
+	#[test]
+	fn free_cash_without_bands_stays_in_band() {
+		let vti = AssetTag::from("VTI");
+		let (_dir, mut pot) = temp_pot("default-band");
+		pot.write_lots(&[lot("main", "VTI", 10.0)]).unwrap();
+		pot.write_targets(&[AssetTag::from("VTI")]).unwrap();
+		pot.write_cash(&AssetTag::usd(), 40.0).unwrap();
+		let prices: HashMap<AssetTag, f64> = vec![(vti.clone(), 100.0), (AssetTag::usd(), 1.0)].into_iter().collect();
+		let status = status(&pot, &prices).unwrap();
+		let row = |asset: &AssetTag| status.rows.iter().find(|row| &row.asset == asset).unwrap();
+		assert!(!row(&AssetTag::usd()).in_band);
+		assert!(row(&vti).in_band);
+		assert!(status.offenders().is_empty());
+	}
+
+	#[test]
+	fn values_lots_by_custodian_and_owner_across_the_tree() {
+		let (_dir, mut pot) = temp_pot("values-by");
+		pot.write_lots(&[lot("brokerage", "VTI", 1.0)]).unwrap();
+		pot.write_cash(&AssetTag::usd(), 50.0).unwrap();
+		add_subpot(&mut pot, "kid", vec![lot("ira", "BND", 4.0), lot("brokerage", "VTI", 2.0)]);
+		let mut config = pot.read_local_config().unwrap();
+		let owned = CustodianConfig { owner: Some("alex".to_string()), ..CustodianConfig::default() };
+		config.custodians.insert("brokerage".to_string(), owned);
+		config.custodians.insert("ira".to_string(), CustodianConfig { account_type: Some(AccountType::TaxDeferred), ..CustodianConfig::default() });
+		pot.write_local_config(&config).unwrap();
+		let prices: HashMap<AssetTag, f64> = vec![
+			(AssetTag::from("VTI"), 100.0), (AssetTag::from("BND"), 50.0), (AssetTag::from(":kid"), 400.0), (AssetTag::usd(), 1.0),
+		].into_iter().collect();
+		let named = |values: Vec<(String, f64)>| values.into_iter().map(|(name, value)| format!("{} {}", name, value)).collect::<Vec<_>>();
+		assert_eq!(named(values_by(&pot, &prices, Grouping::Custodian).unwrap()), vec!["brokerage 300", "cash 50", "ira 200"]);
+		assert_eq!(named(values_by(&pot, &prices, Grouping::Owner).unwrap()), vec!["alex 300", "cash 50", "unassigned 200"]);
+		assert_eq!(named(values_by(&pot, &prices, Grouping::AccountType).unwrap()), vec!["cash 50", "tax-deferred 200", "taxable 300"]);
+	}
+
+	#[test]
+	fn realized_gain_adds_back_disallowed_losses() {
+		let date = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+		let wash = Realized { sale: sale("main", "VTI", date, 800.0, 1000.0), disallowed: 200.0, basis_adjustment: 0.0 };
+		assert_eq!((wash.basis(), wash.gain(), wash.is_wash_sale()), (Some(1000.0), Some(0.0), true));
+		let replacement = Realized { sale: sale("main", "VTI", date, 900.0, 850.0), disallowed: 0.0, basis_adjustment: 200.0 };
+		assert_eq!((replacement.basis(), replacement.gain(), replacement.is_wash_sale()), (Some(1050.0), Some(-150.0), false));
+		let unknown = Realized { sale: Sale { basis: None, ..sale("main", "VTI", date, 900.0, 0.0) }, disallowed: 0.0, basis_adjustment: 0.0 };
+		assert_eq!((unknown.basis(), unknown.gain()), (None, None));
+	}
+
+	#[test]
+	fn realized_sales_roll_up_the_year_across_the_tree() {
+		let (_dir, mut pot) = temp_pot("realized");
+		let date = |year, month| NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+		pot.write_records(&[sale("brokerage", "VTI", date(2026, 3), 500.0, 300.0), sale("brokerage", "VTI", date(2025, 3), 400.0, 300.0)]).unwrap();
+		let kid = add_subpot(&mut pot, "kid", vec![]);
+		kid.write_records(&[sale("ira", "BND", date(2026, 1), 100.0, 150.0)]).unwrap();
+		let mut config = pot.read_local_config().unwrap();
+		config.custodians.insert("brokerage".to_string(), CustodianConfig::default());
+		config.custodians.insert("ira".to_string(), CustodianConfig { account_type: Some(AccountType::TaxDeferred), ..CustodianConfig::default() });
+		pot.write_local_config(&config).unwrap();
+		let all = realized_sales(&pot, 2026, false).unwrap();
+		assert_eq!(all.iter().map(|it| it.gain().unwrap()).collect::<Vec<_>>(), vec![-50.0, 200.0]);
+		let taxable = realized_sales(&pot, 2026, true).unwrap();
+		assert_eq!(taxable.len(), 1);
+		assert_eq!(taxable[0].sale.asset_tag, AssetTag::from("VTI"));
+	}
+
+	#[test]
+	fn values_assets_locally_and_across_the_tree() {
+		let (_dir, mut pot) = temp_pot("values");
+		pot.write_lots(&[lot("main", "VTI", 1.0)]).unwrap();
+		add_subpot(&mut pot, "kid", vec![lot("main", "VTI", 2.0), lot("main", "BND", 4.0)]);
+		let prices: HashMap<AssetTag, f64> = vec![
+			(AssetTag::from("VTI"), 100.0), (AssetTag::from("BND"), 50.0), (AssetTag::from(":kid"), 400.0),
+		].into_iter().collect();
+		let local = market_values(&pot, &prices).unwrap();
+		assert_eq!(local, vec![(AssetTag::from("VTI"), 100.0), (AssetTag::from(":kid"), 400.0)]);
+		let deep = asset_values(&pot, &prices).unwrap();
+		assert_eq!(deep, vec![(AssetTag::from("BND"), 200.0), (AssetTag::from("VTI"), 300.0)]);
+	}
+}
@@ -0,0 +1,191 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::Custodian;
+use crate::asset_tag::AssetTag;
+use crate::date;
+use crate::error::PotError;
+use crate::lot::Lot;
+use crate::pot::Record;
+
+const EPSILON: f64 = 1e-9;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sale {
+	pub uid: u64,
+	#[serde(rename = "custody")]
+	pub custodian: Custodian,
+	#[serde(rename = "symbol")]
+	pub asset_tag: AssetTag,
+	pub count: f64,
+	pub acquired: Option<NaiveDate>,
+	pub sold: NaiveDate,
+	pub proceeds: f64,
+	pub basis: Option<f64>,
+}
+
+impl Record for Sale {
+	const NAME: &'static str = "sales";
+}
+
+impl Sale {
+	pub fn gain(&self) -> Option<f64> { self.basis.map(|basis| self.proceeds - basis) }
+	pub fn term(&self) -> Term {
+		match self.acquired {
+			Some(acquired) if date::is_long_term(acquired, self.sold) => Term::Long,
+			Some(_) => Term::Short,
+			None => Term::Unknown,
+		}
+	}
+	pub fn is_long_term(&self) -> bool { self.term() == Term::Long }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Term { Short, Long, Unknown }
+
+impl Term {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Term::Short => "short",
+			Term::Long => "long",
+			Term::Unknown => "unknown",
+		}
+	}
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ReliefMethod { Fifo, Lifo, Hifo, Specific(u64) }
+
+impl ReliefMethod {
+	pub fn parse(s: &str) -> Option<Self> {
+		let s = s.trim().to_lowercase();
+		match s.as_str() {
+			"fifo" => Some(ReliefMethod::Fifo),
+			"lifo" => Some(ReliefMethod::Lifo),
+			"hifo" => Some(ReliefMethod::Hifo),
+			_ if s.starts_with("specific:") => u64::from_str_radix(&s["specific:".len()..], 16).ok().map(ReliefMethod::Specific),
+			_ => None,
+		}
+	}
+}
+
+pub fn sell(
+	lots: &[Lot], custodian: &str, asset: &AssetTag, count: f64,
+	method: ReliefMethod, price: f64, sold: NaiveDate,
+) -> Result<(Vec<Lot>, Vec<Sale>), PotError> {
+	let mut candidates = lots.iter()
+		.enumerate()
+		.filter(|(_, lot)| lot.has_custodian(custodian) && lot.has_tag(asset) && lot.share_count.as_f64() > 0.0)
+		.filter(|(_, lot)| match method {
+			ReliefMethod::Specific(uid) => lot.uid == uid,
+			_ => true,
+		})
+		.map(|(index, _)| index)
+		.collect::<Vec<_>>();
+	if candidates.is_empty() {
+		return Err(PotError::MissingLot { custodian: custodian.to_string(), symbol: asset.as_str().to_string() });
+	}
+	match method {
+		ReliefMethod::Fifo => candidates.sort_by_key(|&index| lots[index].acquired),
+		ReliefMethod::Lifo => candidates.sort_by_key(|&index| std::cmp::Reverse(lots[index].acquired)),
+		ReliefMethod::Hifo => candidates.sort_by(|&a, &b| {
+			let a = lots[a].basis_per_share().unwrap_or(f64::MIN);
+			let b = lots[b].basis_per_share().unwrap_or(f64::MIN);
+			b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal)
+		}),
+		ReliefMethod::Specific(_) => {}
+	}
+	let held: f64 = candidates.iter().map(|&index| lots[index].share_count.as_f64()).sum();
+	if count > held + EPSILON {
+		return Err(PotError::InsufficientShares {
+			custodian: custodian.to_string(),
+			symbol: asset.as_str().to_string(),
+			held,
+		});
+	}
+	let mut new_lots = lots.iter().cloned().map(Some).collect::<Vec<_>>();
+	let mut sales = Vec::new();
+	let mut remaining = count;
+	for index in candidates {
+		if remaining <= EPSILON {
+			break;
+		}
+		let lot = &lots[index];
+		let (taken, rest) = if lot.share_count.as_f64() <= remaining + EPSILON {
+			(lot.clone(), None)
+		} else {
+			let (taken, rest) = lot.split(remaining);
+			(taken, Some(rest))
+		};
+		remaining -= taken.share_count.as_f64();
+		sales.push(Sale {
+			uid: taken.uid,
+			custodian: taken.custodian.clone(),
+			asset_tag: taken.asset_tag.clone(),
+			count: taken.share_count.as_f64(),
+			acquired: taken.acquired,
+			sold,
+			proceeds: taken.share_count.as_f64() * price,
+			basis: taken.basis,
+		});
+		new_lots[index] = rest;
+	}
+	Ok((new_lots.into_iter().flatten().collect(), sales))
+}
+
+#[cfg(test)]
+mod tests {
+	use chrono::NaiveDate;
+
+	use crate::{Custodian, ShareCount};
+	use crate::asset_tag::AssetTag;
+	use crate::lot::Lot;
+	use crate::sale::{ReliefMethod, Sale, sell, Term};
+
+	fn lot(uid: u64, count: f64, basis: f64, year: i32) -> Lot {
+		Lot {
+			custodian: Custodian("main".to_string()),
+			asset_tag: AssetTag::equity("vti"),
+			share_count: ShareCount(count),
+			uid,
+			basis: Some(basis),
+			acquired: NaiveDate::from_ymd_opt(year, 1, 1),
+		}
+	}
+
+	#[test]
+	fn relief_methods() {
+		let lots = vec![lot(1, 10.0, 1000.0, 2019), lot(2, 10.0, 3000.0, 2021), lot(3, 10.0, 2000.0, 2020)];
+		let sold = NaiveDate::from_ymd_opt(2022, 6, 1).unwrap();
+		let vti = AssetTag::equity("vti");
+		let relieved = |method| {
+			let (_, sales) = sell(&lots, "main", &vti, 5.0, method, 250.0, sold).unwrap();
+			sales[0].uid
+		};
+		assert_eq!(relieved(ReliefMethod::Fifo), 1);
+		assert_eq!(relieved(ReliefMethod::Lifo), 2);
+		assert_eq!(relieved(ReliefMethod::Hifo), 2);
+		assert_eq!(relieved(ReliefMethod::parse("specific:0000000000000003").unwrap()), 3);
+		assert_eq!(ReliefMethod::parse(&format!("specific:{:016x}", 255)), Some(ReliefMethod::Specific(255)));
+	}
+
+	#[test]
+	fn splits_partial_lots() {
+		let lots = vec![lot(1, 10.0, 1000.0, 2019), lot(2, 10.0, 3000.0, 2021)];
+		let sold = NaiveDate::from_ymd_opt(2021, 6, 1).unwrap();
+		let (new_lots, sales) = sell(&lots, "main", &AssetTag::equity("vti"), 15.0, ReliefMethod::Fifo, 250.0, sold).unwrap();
+		assert_eq!(sales.len(), 2);
+		assert_eq!(sales[0].gain(), Some(1500.0));
+		assert!(sales[0].is_long_term());
+		assert_eq!(sales[1].gain(), Some(-250.0));
+		assert_eq!(sales[1].term(), Term::Short);
+		let undated = Sale { acquired: None, ..sales[0].clone() };
+		assert_eq!(undated.term(), Term::Unknown);
+		assert!(!undated.is_long_term());
+		assert_eq!(new_lots.len(), 1);
+		assert_eq!(new_lots[0].share_count.as_f64(), 5.0);
+		assert_eq!(new_lots[0].basis, Some(1500.0));
+		assert_ne!(new_lots[0].uid, 2);
+		assert!(sell(&lots, "main", &AssetTag::equity("vti"), 25.0, ReliefMethod::Fifo, 250.0, sold).is_err());
+	}
+}
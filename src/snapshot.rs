@@ -0,0 +1,195 @@
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::action::{ActionKind, CorporateAction};
+use crate::asset_tag::AssetTag;
+use crate::core::PotPath;
+use crate::pot::Record;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+	pub date: NaiveDate,
+	pub pot: PotPath,
+	pub symbol: AssetTag,
+	pub shares: f64,
+	pub price: f64,
+}
+
+impl Record for Snapshot {
+	const NAME: &'static str = "snapshots";
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct PeriodReturn {
+	pub from: NaiveDate,
+	pub to: NaiveDate,
+	pub actual: f64,
+	pub benchmark: f64,
+}
+
+impl PeriodReturn {
+	pub fn difference(&self) -> f64 { self.actual - self.benchmark }
+}
+
+pub fn dates(snapshots: &[Snapshot]) -> Vec<NaiveDate> {
+	snapshots.iter().map(|snapshot| snapshot.date).collect::<BTreeSet<_>>().into_iter().collect()
+}
+
+// Compares each pot's buy-and-hold return between consecutive snapshots with the
+// return of its benchmark weights over the same period. Subpots held by a pot grow
+// with their own return, and subpots in a benchmark with their own benchmark, both
+// found by full pot path. Prices are adjusted for splits recorded between the two
+// snapshots.
+pub fn compare(
+	snapshots: &[Snapshot], actions: &[CorporateAction], weights: &HashMap<PotPath, HashMap<AssetTag, f64>>, pot: &PotPath,
+) -> Vec<PeriodReturn> {
+	let dates = dates(snapshots);
+	dates.windows(2)
+		.filter_map(|period| {
+			let (from, to) = (period[0], period[1]);
+			let actual = actual_return(snapshots, actions, pot, from, to)?;
+			let benchmark = benchmark_return(snapshots, actions, weights, pot, from, to)?;
+			Some(PeriodReturn { from, to, actual, benchmark })
+		})
+		.collect()
+}
+
+pub fn actual_return(snapshots: &[Snapshot], actions: &[CorporateAction], pot: &PotPath, from: NaiveDate, to: NaiveDate) -> Option<f64> {
+	let (mut start, mut end) = (0.0, 0.0);
+	for row in snapshots.iter().filter(|row| row.date == from && &row.pot == pot) {
+		let growth = if row.symbol.is_subpot() {
+			subpot_path(pot, &row.symbol)
+				.and_then(|subpot| actual_return(snapshots, actions, &subpot, from, to))
+				.map(|rate| 1.0 + rate)
+		} else {
+			price_growth(snapshots, actions, pot, &row.symbol, from, to)
+		};
+		if let Some(growth) = growth {
+			start += row.shares * row.price;
+			end += row.shares * row.price * growth;
+		}
+	}
+	if start > 0.0 { Some(end / start - 1.0) } else { None }
+}
+
+pub fn benchmark_return(
+	snapshots: &[Snapshot], actions: &[CorporateAction], weights: &HashMap<PotPath, HashMap<AssetTag, f64>>,
+	pot: &PotPath, from: NaiveDate, to: NaiveDate,
+) -> Option<f64> {
+	let (mut total, mut grown) = (0.0, 0.0);
+	for (symbol, weight) in weights.get(pot)? {
+		let growth = if symbol.is_subpot() {
+			subpot_path(pot, symbol)
+				.and_then(|subpot| benchmark_return(snapshots, actions, weights, &subpot, from, to))
+				.map(|rate| 1.0 + rate)
+		} else {
+			price_growth(snapshots, actions, pot, symbol, from, to)
+		};
+		if let Some(growth) = growth {
+			total += weight;
+			grown += weight * growth;
+		}
+	}
+	if total > 0.0 { Some(grown / total - 1.0) } else { None }
+}
+
+fn subpot_path(pot: &PotPath, tag: &AssetTag) -> Option<PotPath> {
+	tag.as_folder_name().ok().map(|name| pot.extend(name))
+}
+
+fn price_growth(
+	snapshots: &[Snapshot], actions: &[CorporateAction], pot: &PotPath, symbol: &AssetTag, from: NaiveDate, to: NaiveDate,
+) -> Option<f64> {
+	let price = |date| snapshots.iter()
+		.find(|row| row.date == date && &row.pot == pot && &row.symbol == symbol && row.price > 0.0)
+		.map(|row| row.price);
+	let split: f64 = actions.iter()
+		.filter(|action| action.kind == ActionKind::Split && &action.symbol == symbol)
+		.filter(|action| from < action.date && action.date <= to)
+		.filter_map(|action| action.ratio)
+		.product();
+	Some(price(to)? / (price(from)? / split))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use chrono::NaiveDate;
+
+	use crate::action::CorporateAction;
+	use crate::asset_tag::AssetTag;
+	use crate::core::PotPath;
+	use crate::snapshot::{actual_return, compare, Snapshot};
+
+	#[test]
+	fn compares_holdings_with_benchmark_weights() {
+		let (vti, bnd, kid) = (AssetTag::equity("vti"), AssetTag::equity("bnd"), AssetTag::from(":kid"));
+		let (root, kid_path) = (PotPath::CurrentFolder, PotPath::CurrentFolder.extend("kid"));
+		let row = |month, pot: &PotPath, symbol: &AssetTag, shares, price| Snapshot {
+			date: NaiveDate::from_ymd_opt(2025, month, 1).unwrap(),
+			pot: pot.clone(),
+			symbol: symbol.clone(),
+			shares,
+			price,
+		};
+		let snapshots = [
+			row(1, &root, &vti, 10.0, 100.0), row(1, &root, &bnd, 0.0, 50.0), row(1, &root, &kid, 1.0, 1000.0),
+			row(1, &kid_path, &bnd, 20.0, 50.0),
+			row(2, &root, &vti, 10.0, 120.0), row(2, &root, &bnd, 0.0, 55.0), row(2, &root, &kid, 1.0, 1500.0),
+			row(2, &kid_path, &bnd, 20.0, 55.0),
+		];
+		let mut weights = HashMap::new();
+		weights.insert(root.clone(), vec![(vti.clone(), 0.5), (kid.clone(), 0.5)].into_iter().collect());
+		weights.insert(kid_path.clone(), vec![(bnd.clone(), 1.0)].into_iter().collect());
+		let periods = compare(&snapshots, &[], &weights, &root);
+		assert_eq!(periods.len(), 1);
+		assert!((periods[0].actual - 0.15).abs() < 1e-9);
+		assert!((periods[0].benchmark - 0.15).abs() < 1e-9);
+		weights.insert(root.clone(), vec![(bnd.clone(), 1.0)].into_iter().collect());
+		assert!((compare(&snapshots, &[], &weights, &root)[0].difference() - 0.05).abs() < 1e-9);
+	}
+
+	#[test]
+	fn adjusts_prices_for_splits_between_snapshots() {
+		let (nvda, root) = (AssetTag::equity("nvda"), PotPath::CurrentFolder);
+		let row = |month, shares, price| Snapshot {
+			date: NaiveDate::from_ymd_opt(2024, month, 1).unwrap(),
+			pot: root.clone(),
+			symbol: nvda.clone(),
+			shares,
+			price,
+		};
+		let snapshots = [row(5, 10.0, 1000.0), row(7, 100.0, 120.0), row(8, 100.0, 132.0)];
+		let split = CorporateAction::split(nvda.clone(), 10.0, NaiveDate::from_ymd_opt(2024, 6, 10).unwrap());
+		let weights = vec![(root.clone(), vec![(nvda.clone(), 1.0)].into_iter().collect())].into_iter().collect();
+		let periods = compare(&snapshots, &[split], &weights, &root);
+		assert_eq!(periods.len(), 2);
+		assert!((periods[0].actual - 0.2).abs() < 1e-9);
+		assert!((periods[0].benchmark - 0.2).abs() < 1e-9);
+		assert!((periods[1].actual - 0.1).abs() < 1e-9);
+	}
+
+	#[test]
+	fn keeps_same_named_subpots_apart() {
+		let (vti, bnd, kid) = (AssetTag::equity("vti"), AssetTag::equity("bnd"), AssetTag::from(":kid"));
+		let (a, b) = (PotPath::CurrentFolder.extend("a"), PotPath::CurrentFolder.extend("b"));
+		let (a_kid, b_kid) = (a.extend("kid"), b.extend("kid"));
+		let row = |month, pot: &PotPath, symbol: &AssetTag, price| Snapshot {
+			date: NaiveDate::from_ymd_opt(2025, month, 1).unwrap(),
+			pot: pot.clone(),
+			symbol: symbol.clone(),
+			shares: 1.0,
+			price,
+		};
+		let snapshots = [
+			row(1, &a, &kid, 100.0), row(1, &a_kid, &vti, 100.0), row(1, &b, &kid, 50.0), row(1, &b_kid, &bnd, 50.0),
+			row(2, &a, &kid, 110.0), row(2, &a_kid, &vti, 110.0), row(2, &b, &kid, 60.0), row(2, &b_kid, &bnd, 60.0),
+		];
+		let (from, to) = (NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(), NaiveDate::from_ymd_opt(2025, 2, 1).unwrap());
+		assert!((actual_return(&snapshots, &[], &a, from, to).unwrap() - 0.1).abs() < 1e-9);
+		assert!((actual_return(&snapshots, &[], &b, from, to).unwrap() - 0.2).abs() < 1e-9);
+	}
+}
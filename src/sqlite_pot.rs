@@ -0,0 +1,478 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use rusqlite::{Connection, params};
+
+use crate::{Custodian, ShareCount};
+use crate::asset_tag::AssetTag;
+use crate::config::PotConfig;
+use crate::core::PotPath;
+use crate::date::parse_date;
+use crate::error::PotError;
+use crate::lot::Lot;
+use crate::metadata::AssetMetadata;
+use crate::pot::{Pot, Record};
+
+pub const DATABASE_FILE: &str = "pot.sqlite";
+
+const SCHEMA_VERSION: u32 = 4;
+
+const SCHEMA: &str = "
+	CREATE TABLE IF NOT EXISTS pots (
+		path TEXT PRIMARY KEY,
+		parent TEXT,
+		config TEXT NOT NULL DEFAULT ''
+	);
+	CREATE TABLE IF NOT EXISTS cash (
+		pot TEXT NOT NULL,
+		currency TEXT NOT NULL,
+		amount REAL NOT NULL,
+		PRIMARY KEY (pot, currency)
+	);
+	CREATE TABLE IF NOT EXISTS lots (
+		pot TEXT NOT NULL,
+		position INTEGER NOT NULL,
+		uid INTEGER NOT NULL,
+		custody TEXT NOT NULL,
+		symbol TEXT NOT NULL,
+		count REAL NOT NULL,
+		basis REAL,
+		acquired TEXT,
+		PRIMARY KEY (pot, position)
+	);
+	CREATE TABLE IF NOT EXISTS records (
+		pot TEXT NOT NULL,
+		kind TEXT NOT NULL,
+		position INTEGER NOT NULL,
+		data TEXT NOT NULL,
+		PRIMARY KEY (pot, kind, position)
+	);
+	CREATE TABLE IF NOT EXISTS targets (
+		pot TEXT NOT NULL,
+		position INTEGER NOT NULL,
+		symbol TEXT NOT NULL,
+		PRIMARY KEY (pot, position)
+	);
+	CREATE TABLE IF NOT EXISTS metadata (
+		pot TEXT NOT NULL,
+		symbol TEXT NOT NULL,
+		category TEXT NOT NULL,
+		value TEXT NOT NULL,
+		PRIMARY KEY (pot, symbol, category)
+	);
+";
+
+#[derive(Clone)]
+pub struct SqlitePot {
+	conn: Rc<Connection>,
+	path: Rc<PathBuf>,
+	pot_path: PotPath,
+}
+
+impl SqlitePot {
+	pub fn new() -> Result<Self, PotError> {
+		SqlitePot::open(Path::new(DATABASE_FILE))
+	}
+	pub fn open(path: &Path) -> Result<Self, PotError> {
+		let fail = |source| PotError::Database { path: path.to_owned(), source };
+		let conn = Connection::open(path).map_err(fail)?;
+		let version = conn.query_row("PRAGMA user_version", params![], |row| row.get::<_, u32>(0)).map_err(fail)?;
+		if version > SCHEMA_VERSION {
+			return Err(PotError::UnsupportedFormat { path: path.to_owned(), version, supported: SCHEMA_VERSION });
+		}
+		conn.execute_batch(SCHEMA).map_err(fail)?;
+		if version < 1 {
+			move_ramp_into_config(&conn).map_err(fail)?;
+		}
+		if version < 2 {
+			move_cash_into_table(&conn).map_err(fail)?;
+		}
+		if version < 4 {
+			add_lot_basis(&conn).map_err(fail)?;
+		}
+		conn.execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION)).map_err(fail)?;
+		Ok(SqlitePot { conn: Rc::new(conn), path: Rc::new(path.to_owned()), pot_path: PotPath::CurrentFolder })
+	}
+
+	pub fn path(&self) -> &Path { &self.path }
+
+	fn key(&self) -> String { path_key(&self.pot_path) }
+	fn parent_key(&self) -> Option<String> {
+		match &self.pot_path {
+			PotPath::CurrentFolder => None,
+			PotPath::SubFolder(parent, _) => Some(path_key(parent)),
+		}
+	}
+	fn db<T>(&self, result: rusqlite::Result<T>) -> Result<T, PotError> {
+		result.map_err(|source| PotError::Database { path: self.path.as_ref().to_owned(), source })
+	}
+	fn insert_row(&self) -> Result<(), PotError> {
+		self.db(self.conn.execute(
+			"INSERT OR IGNORE INTO pots (path, parent) VALUES (?1, ?2)",
+			params![self.key(), self.parent_key()],
+		))?;
+		Ok(())
+	}
+}
+
+fn move_ramp_into_config(conn: &Connection) -> rusqlite::Result<()> {
+	let has_ramp = conn.prepare("SELECT ramp FROM pots").is_ok();
+	if has_ramp {
+		conn.execute_batch("
+			ALTER TABLE pots ADD COLUMN config TEXT NOT NULL DEFAULT '';
+			UPDATE pots SET config = 'ramp = \"' || ramp || '\"\n';
+		")?;
+	}
+	Ok(())
+}
+
+fn move_cash_into_table(conn: &Connection) -> rusqlite::Result<()> {
+	let has_cash = conn.prepare("SELECT cash FROM pots").is_ok();
+	if has_cash {
+		conn.execute_batch("INSERT OR REPLACE INTO cash (pot, currency, amount) SELECT path, 'USD', cash FROM pots")?;
+	}
+	Ok(())
+}
+
+fn add_lot_basis(conn: &Connection) -> rusqlite::Result<()> {
+	let has_basis = conn.prepare("SELECT basis FROM lots").is_ok();
+	if !has_basis {
+		conn.execute_batch("
+			ALTER TABLE lots ADD COLUMN basis REAL;
+			ALTER TABLE lots ADD COLUMN acquired TEXT;
+		")?;
+	}
+	Ok(())
+}
+
+fn path_key(pot_path: &PotPath) -> String {
+	pot_path.segment_names()
+		.into_iter()
+		.filter(|it| !it.is_empty())
+		.collect::<Vec<_>>()
+		.join("::")
+}
+
+impl Pot for SqlitePot {
+	fn is_not_initialized(&self) -> bool {
+		let count = self.conn.query_row(
+			"SELECT COUNT(*) FROM pots WHERE path = ?1",
+			params![self.key()],
+			|row| row.get::<_, i64>(0),
+		);
+		count.map(|count| count == 0).unwrap_or(true)
+	}
+	fn init_if_not(&mut self) -> Result<(), PotError> {
+		if self.is_not_initialized() {
+			self.init()
+		} else {
+			Ok(())
+		}
+	}
+	fn init(&mut self) -> Result<(), PotError> {
+		self.insert_row()?;
+		self.write_lots(&Vec::new())?;
+		self.write_cash_balances(&BTreeMap::new())?;
+		Ok(())
+	}
+
+	fn subpot(&self, name: &str) -> Box<Self> {
+		Box::new(SqlitePot {
+			conn: self.conn.clone(),
+			path: self.path.clone(),
+			pot_path: self.pot_path.extend(name),
+		})
+	}
+	fn parent(&self) -> Option<Self> {
+		match &self.pot_path {
+			PotPath::CurrentFolder => None,
+			PotPath::SubFolder(parent, _) => Some(SqlitePot {
+				conn: self.conn.clone(),
+				path: self.path.clone(),
+				pot_path: parent.as_ref().clone(),
+			}),
+		}
+	}
+	fn pot_path(&self) -> &PotPath { &self.pot_path }
+
+	fn read_cash_balances(&self) -> Result<BTreeMap<AssetTag, f64>, PotError> {
+		let mut stmt = self.db(self.conn.prepare("SELECT currency, amount FROM cash WHERE pot = ?1"))?;
+		let rows = self.db(stmt.query_map(params![self.key()], |row| {
+			Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+		}))?;
+		let mut balances = BTreeMap::new();
+		for row in rows {
+			let (currency, amount) = self.db(row)?;
+			balances.insert(AssetTag::currency(&currency), amount);
+		}
+		Ok(balances)
+	}
+
+	fn write_cash_balances(&self, balances: &BTreeMap<AssetTag, f64>) -> Result<(), PotError> {
+		self.insert_row()?;
+		let tx = self.db(self.conn.unchecked_transaction())?;
+		self.db(tx.execute("DELETE FROM cash WHERE pot = ?1", params![self.key()]))?;
+		for (currency, amount) in balances {
+			self.db(tx.execute(
+				"INSERT INTO cash (pot, currency, amount) VALUES (?1, ?2, ?3)",
+				params![self.key(), currency.symbol(), amount],
+			))?;
+		}
+		self.db(tx.commit())?;
+		Ok(())
+	}
+
+	fn read_local_config(&self) -> Result<PotConfig, PotError> {
+		let string = match self.conn.query_row(
+			"SELECT config FROM pots WHERE path = ?1",
+			params![self.key()],
+			|row| row.get::<_, String>(0),
+		) {
+			Err(rusqlite::Error::QueryReturnedNoRows) => String::new(),
+			result => self.db(result)?,
+		};
+		toml::from_str(&string).map_err(|err| PotError::parse(&self.path, None, err))
+	}
+
+	fn write_local_config(&self, config: &PotConfig) -> Result<(), PotError> {
+		let string = toml::to_string(config).map_err(|err| PotError::parse(&self.path, None, err))?;
+		self.insert_row()?;
+		self.db(self.conn.execute("UPDATE pots SET config = ?1 WHERE path = ?2", params![string, self.key()]))?;
+		Ok(())
+	}
+
+	fn read_lots(&self) -> Result<Vec<Lot>, PotError> {
+		let mut stmt = self.db(self.conn.prepare(
+			"SELECT uid, custody, symbol, count, basis, acquired FROM lots WHERE pot = ?1 ORDER BY position"
+		))?;
+		let rows = self.db(stmt.query_map(params![self.key()], |row| {
+			let uid: i64 = row.get(0)?;
+			let custody: String = row.get(1)?;
+			let symbol: String = row.get(2)?;
+			let count: f64 = row.get(3)?;
+			let acquired: Option<String> = row.get(5)?;
+			Ok(Lot {
+				custodian: Custodian(custody),
+				asset_tag: AssetTag::from(symbol),
+				share_count: ShareCount(count),
+				uid: uid as u64,
+				basis: row.get(4)?,
+				acquired: acquired.and_then(|it| parse_date(&it)),
+			})
+		}))?;
+		let mut lots = Vec::new();
+		for lot in rows {
+			lots.push(self.db(lot)?);
+		}
+		Ok(lots)
+	}
+	fn write_lots(&mut self, lots: &[Lot]) -> Result<(), PotError> {
+		let tx = self.db(self.conn.unchecked_transaction())?;
+		self.db(tx.execute("DELETE FROM lots WHERE pot = ?1", params![self.key()]))?;
+		for (position, lot) in lots.iter().enumerate() {
+			self.db(tx.execute(
+				"INSERT INTO lots (pot, position, uid, custody, symbol, count, basis, acquired) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+				params![
+					self.key(), position as i64, lot.uid as i64,
+					lot.custodian.as_str(), lot.asset_tag.as_str(), lot.share_count.as_f64(),
+					lot.basis, lot.acquired.map(|it| it.to_string())
+				],
+			))?;
+		}
+		self.db(tx.commit())?;
+		Ok(())
+	}
+
+	fn read_targets(&self) -> Result<Vec<AssetTag>, PotError> {
+		let mut stmt = self.db(self.conn.prepare("SELECT symbol FROM targets WHERE pot = ?1 ORDER BY position"))?;
+		let rows = self.db(stmt.query_map(params![self.key()], |row| row.get::<_, String>(0)))?;
+		let mut targets = Vec::new();
+		for symbol in rows {
+			targets.push(AssetTag::from(self.db(symbol)?));
+		}
+		Ok(targets)
+	}
+	fn write_targets(&self, targets: &[AssetTag]) -> Result<(), PotError> {
+		let tx = self.db(self.conn.unchecked_transaction())?;
+		self.db(tx.execute("DELETE FROM targets WHERE pot = ?1", params![self.key()]))?;
+		for (position, target) in targets.iter().enumerate() {
+			self.db(tx.execute(
+				"INSERT INTO targets (pot, position, symbol) VALUES (?1, ?2, ?3)",
+				params![self.key(), position as i64, target.as_str()],
+			))?;
+		}
+		self.db(tx.commit())?;
+		Ok(())
+	}
+
+	fn read_asset_metadata(&self) -> Result<AssetMetadata, PotError> {
+		let mut stmt = self.db(self.conn.prepare("SELECT symbol, category, value FROM metadata WHERE pot = ?1"))?;
+		let rows = self.db(stmt.query_map(params![self.key()], |row| {
+			Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+		}))?;
+		let mut metadata = AssetMetadata::default();
+		for row in rows {
+			let (symbol, category, value) = self.db(row)?;
+			metadata.set_category(&AssetTag::from(symbol), &category, &value);
+		}
+		Ok(metadata)
+	}
+	fn write_asset_metadata(&self, metadata: &AssetMetadata) -> Result<(), PotError> {
+		let tx = self.db(self.conn.unchecked_transaction())?;
+		self.db(tx.execute("DELETE FROM metadata WHERE pot = ?1", params![self.key()]))?;
+		for (asset, categories) in &metadata.0 {
+			for (category, value) in categories {
+				self.db(tx.execute(
+					"INSERT INTO metadata (pot, symbol, category, value) VALUES (?1, ?2, ?3, ?4)",
+					params![self.key(), asset.as_str(), category, value],
+				))?;
+			}
+		}
+		self.db(tx.commit())?;
+		Ok(())
+	}
+
+	fn read_records<R: Record>(&self) -> Result<Vec<R>, PotError> {
+		let mut stmt = self.db(self.conn.prepare(
+			"SELECT data FROM records WHERE pot = ?1 AND kind = ?2 ORDER BY position"
+		))?;
+		let rows = self.db(stmt.query_map(params![self.key(), R::NAME], |row| row.get::<_, String>(0)))?;
+		let mut records = Vec::new();
+		for row in rows {
+			let data = self.db(row)?;
+			let mut rdr = csv::ReaderBuilder::new().has_headers(false).from_reader(data.as_bytes());
+			for result in rdr.deserialize() {
+				records.push(result.map_err(|err| PotError::csv(&self.path, err))?);
+			}
+		}
+		Ok(records)
+	}
+	fn write_records<R: Record>(&self, records: &[R]) -> Result<(), PotError> {
+		let tx = self.db(self.conn.unchecked_transaction())?;
+		self.db(tx.execute("DELETE FROM records WHERE pot = ?1 AND kind = ?2", params![self.key(), R::NAME]))?;
+		for (position, record) in records.iter().enumerate() {
+			let mut wtr = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+			wtr.serialize(record).map_err(|err| PotError::csv(&self.path, err))?;
+			let data = wtr.into_inner().map_err(|err| PotError::io(&self.path, err.into_error()))?;
+			self.db(tx.execute(
+				"INSERT INTO records (pot, kind, position, data) VALUES (?1, ?2, ?3, ?4)",
+				params![self.key(), R::NAME, position as i64, String::from_utf8_lossy(&data).trim_end()],
+			))?;
+		}
+		self.db(tx.commit())?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use chrono::NaiveDate;
+
+	use crate::action::{ActionKind, CorporateAction};
+	use crate::asset_tag::AssetTag;
+	use crate::core::{PotPath, Ramp};
+	use crate::error::PotError;
+	use crate::lot::Lot;
+	use crate::metadata::AssetMetadata;
+	use crate::pot::Pot;
+	use crate::sale::Sale;
+	use crate::snapshot::Snapshot;
+	use crate::sqlite_pot::{DATABASE_FILE, SCHEMA_VERSION, SqlitePot};
+	use crate::testing::TempDir;
+	use crate::{Custodian, ShareCount};
+
+	#[test]
+	fn round_trip() {
+		let mut pot = SqlitePot::open(Path::new(":memory:")).unwrap();
+		pot.init().unwrap();
+		let lot = Lot {
+			custodian: Custodian("main".to_string()),
+			asset_tag: AssetTag::equity("vti"),
+			share_count: ShareCount(12.5),
+			uid: u64::MAX,
+			basis: Some(1000.0),
+			acquired: NaiveDate::from_ymd_opt(2020, 3, 16),
+		};
+		pot.write_lots(&[lot]).unwrap();
+		pot.write_cash(&AssetTag::usd(), 100.0).unwrap();
+		pot.write_cash(&AssetTag::currency("eur"), 20.0).unwrap();
+		pot.write_ramp(Ramp::Flat).unwrap();
+		pot.write_targets(&[AssetTag::equity("bnd"), AssetTag::equity("vti")]).unwrap();
+		let mut metadata = AssetMetadata::default();
+		metadata.set_category(&AssetTag::equity("vti"), "region", "us");
+		pot.write_asset_metadata(&metadata).unwrap();
+		pot.write_records(&[Sale {
+			uid: 7,
+			custodian: Custodian("main, taxable".to_string()),
+			asset_tag: AssetTag::equity("vti"),
+			count: 2.0,
+			acquired: None,
+			sold: NaiveDate::from_ymd_opt(2021, 6, 1).unwrap(),
+			proceeds: 450.0,
+			basis: Some(400.0),
+		}]).unwrap();
+		pot.write_records(&[CorporateAction {
+			date: NaiveDate::from_ymd_opt(2022, 6, 9).unwrap(),
+			kind: ActionKind::Rename,
+			symbol: AssetTag::equity("fb"),
+			ratio: None,
+			renamed: Some(AssetTag::equity("meta")),
+		}]).unwrap();
+
+		let mut sub = pot.subpot("house");
+		sub.init().unwrap();
+		sub.write_cash(&AssetTag::usd(), 5.0).unwrap();
+		pot.write_records(&[Snapshot {
+			date: NaiveDate::from_ymd_opt(2023, 1, 2).unwrap(),
+			pot: sub.pot_path().clone(),
+			symbol: AssetTag::equity("vti"),
+			shares: 3.0,
+			price: 200.0,
+		}]).unwrap();
+
+		let lots = pot.read_lots().unwrap();
+		assert_eq!(lots[0].uid, u64::MAX);
+		assert_eq!(lots[0].share_count.as_f64(), 12.5);
+		assert_eq!(lots[0].basis, Some(1000.0));
+		assert_eq!(lots[0].acquired, NaiveDate::from_ymd_opt(2020, 3, 16));
+		assert_eq!(pot.read_cash(&AssetTag::usd()).unwrap(), 100.0);
+		assert_eq!(pot.read_cash(&AssetTag::from("cash:EUR")).unwrap(), 20.0);
+		assert_eq!(pot.read_ramp().unwrap(), Ramp::Flat);
+		assert_eq!(pot.read_targets().unwrap(), vec![AssetTag::equity("bnd"), AssetTag::equity("vti")]);
+		assert_eq!(pot.read_asset_metadata().unwrap().category(&AssetTag::equity("vti"), "region"), Some("us"));
+		let sales = pot.read_records::<Sale>().unwrap();
+		assert_eq!(sales[0].custodian.as_str(), "main, taxable");
+		assert_eq!(sales[0].acquired, None);
+		assert_eq!(sales[0].basis, Some(400.0));
+		let actions = pot.read_records::<CorporateAction>().unwrap();
+		assert_eq!(actions[0].kind, ActionKind::Rename);
+		assert_eq!(actions[0].renamed, Some(AssetTag::equity("meta")));
+		assert_eq!(pot.read_records::<Snapshot>().unwrap()[0].pot, PotPath::parse("house"));
+		assert_eq!(sub.read_cash(&AssetTag::usd()).unwrap(), 5.0);
+		assert!(sub.read_lots().unwrap().is_empty());
+		assert!(sub.read_records::<Sale>().unwrap().is_empty());
+	}
+
+	#[test]
+	fn config_reads_default_only_for_missing_pots() {
+		let pot = SqlitePot::open(Path::new(":memory:")).unwrap();
+		assert!(pot.subpot("missing").read_local_config().unwrap().ramp.is_none());
+		pot.conn.execute_batch("DROP TABLE pots").unwrap();
+		assert!(matches!(pot.read_local_config(), Err(PotError::Database { .. })));
+	}
+
+	#[test]
+	fn rejects_newer_schemas_with_the_schema_version() {
+		let dir = TempDir::new("sqlite-schema");
+		let path = dir.path().join(DATABASE_FILE);
+		let pot = SqlitePot::open(&path).unwrap();
+		pot.conn.execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION + 1)).unwrap();
+		drop(pot);
+		let err = SqlitePot::open(&path).err().unwrap();
+		assert!(matches!(err, PotError::UnsupportedFormat { supported: SCHEMA_VERSION, .. }));
+		assert!(err.to_string().ends_with(&format!("up to format {}", SCHEMA_VERSION)));
+	}
+}
@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::asset_tag::AssetTag;
+use crate::config::PotConfig;
+use crate::core::PotPath;
+use crate::error::PotError;
+use crate::lot::Lot;
+use crate::metadata::AssetMetadata;
+use crate::pot::{FolderPot, Pot, Record};
+use crate::sqlite_pot::{DATABASE_FILE, SqlitePot};
+
+// The pot in the current folder, kept in pot.sqlite once it has been migrated there
+// and in folder files otherwise.
+#[derive(Clone)]
+pub enum StoredPot {
+	Folder(FolderPot),
+	Sqlite(SqlitePot),
+}
+
+macro_rules! each {
+	($pot:expr, $it:ident => $body:expr) => {
+		match $pot {
+			StoredPot::Folder($it) => $body,
+			StoredPot::Sqlite($it) => $body,
+		}
+	};
+}
+
+impl StoredPot {
+	pub fn open() -> Result<Self, PotError> {
+		StoredPot::open_in(Path::new("."))
+	}
+	pub fn open_in(folder: &Path) -> Result<Self, PotError> {
+		let database = folder.join(DATABASE_FILE);
+		if database.exists() {
+			Ok(StoredPot::Sqlite(SqlitePot::open(&database)?))
+		} else {
+			Ok(StoredPot::Folder(FolderPot::from_path(folder)))
+		}
+	}
+	pub fn path(&self) -> &Path { each!(self, pot => pot.path()) }
+}
+
+impl Pot for StoredPot {
+	fn is_not_initialized(&self) -> bool { each!(self, pot => pot.is_not_initialized()) }
+	fn init_if_not(&mut self) -> Result<(), PotError> { each!(self, pot => pot.init_if_not()) }
+	fn init(&mut self) -> Result<(), PotError> { each!(self, pot => pot.init()) }
+	fn subpot(&self, name: &str) -> Box<Self> {
+		match self {
+			StoredPot::Folder(pot) => Box::new(StoredPot::Folder(*pot.subpot(name))),
+			StoredPot::Sqlite(pot) => Box::new(StoredPot::Sqlite(*pot.subpot(name))),
+		}
+	}
+	fn parent(&self) -> Option<Self> {
+		match self {
+			StoredPot::Folder(pot) => pot.parent().map(StoredPot::Folder),
+			StoredPot::Sqlite(pot) => pot.parent().map(StoredPot::Sqlite),
+		}
+	}
+	fn pot_path(&self) -> &PotPath { each!(self, pot => pot.pot_path()) }
+
+	fn read_cash_balances(&self) -> Result<BTreeMap<AssetTag, f64>, PotError> { each!(self, pot => pot.read_cash_balances()) }
+	fn write_cash_balances(&self, balances: &BTreeMap<AssetTag, f64>) -> Result<(), PotError> {
+		each!(self, pot => pot.write_cash_balances(balances))
+	}
+
+	fn read_local_config(&self) -> Result<PotConfig, PotError> { each!(self, pot => pot.read_local_config()) }
+	fn write_local_config(&self, config: &PotConfig) -> Result<(), PotError> { each!(self, pot => pot.write_local_config(config)) }
+
+	fn read_lots(&self) -> Result<Vec<Lot>, PotError> { each!(self, pot => pot.read_lots()) }
+	fn write_lots(&mut self, lots: &[Lot]) -> Result<(), PotError> { each!(self, pot => pot.write_lots(lots)) }
+
+	fn read_targets(&self) -> Result<Vec<AssetTag>, PotError> { each!(self, pot => pot.read_targets()) }
+	fn write_targets(&self, targets: &[AssetTag]) -> Result<(), PotError> { each!(self, pot => pot.write_targets(targets)) }
+
+	fn read_asset_metadata(&self) -> Result<AssetMetadata, PotError> { each!(self, pot => pot.read_asset_metadata()) }
+	fn write_asset_metadata(&self, metadata: &AssetMetadata) -> Result<(), PotError> {
+		each!(self, pot => pot.write_asset_metadata(metadata))
+	}
+
+	fn read_records<R: Record>(&self) -> Result<Vec<R>, PotError> { each!(self, pot => pot.read_records()) }
+	fn write_records<R: Record>(&self, records: &[R]) -> Result<(), PotError> { each!(self, pot => pot.write_records(records)) }
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::pot::Pot;
+	use crate::stored_pot::StoredPot;
+	use crate::sqlite_pot::{DATABASE_FILE, SqlitePot};
+	use crate::testing::TempDir;
+
+	#[test]
+	fn opens_the_database_once_it_exists() {
+		let dir = TempDir::new("stored");
+		let path = dir.path();
+		assert!(matches!(StoredPot::open_in(path).unwrap(), StoredPot::Folder(_)));
+		SqlitePot::open(&path.join(DATABASE_FILE)).unwrap().init().unwrap();
+		let pot = StoredPot::open_in(path).unwrap();
+		assert!(matches!(pot, StoredPot::Sqlite(_)));
+		assert!(!pot.is_not_initialized());
+		assert!(matches!(pot.subpot("kid").parent(), Some(StoredPot::Sqlite(_))));
+	}
+}
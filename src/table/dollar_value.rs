@@ -2,31 +2,54 @@ use crate::table::Column;
 
 pub struct DollarValueColumn {
 	rows: Vec<f64>,
+	symbol: String,
 }
 
 impl DollarValueColumn {
-	pub fn new(rows: &Vec<f64>) -> Self {
-		DollarValueColumn { rows: rows.to_owned() }
+	pub fn new(rows: &Vec<f64>, symbol: &str) -> Self {
+		DollarValueColumn { rows: rows.to_owned(), symbol: symbol.to_string() }
 	}
 }
 
 impl Column for DollarValueColumn {
 	fn rows(&self) -> usize { self.rows.len() }
 	fn printout(&self, row: usize) -> String {
-		let text = shorten_dollars(self.rows.get(row).cloned().unwrap_or(0.0));
+		let text = shorten_dollars(&self.symbol, self.rows.get(row).cloned().unwrap_or(0.0));
 		format!("{:>6}", text)
 	}
 }
 
-pub fn shorten_dollars(no: f64) -> String {
+pub fn currency_symbol(code: &str) -> &str {
+	match code {
+		"USD" | "CAD" | "AUD" | "NZD" | "HKD" | "SGD" | "MXN" => "$",
+		"EUR" => "€",
+		"GBP" => "£",
+		"JPY" | "CNY" => "¥",
+		"INR" => "₹",
+		"KRW" => "₩",
+		_ => code,
+	}
+}
+
+pub fn shorten_dollars(symbol: &str, no: f64) -> String {
 	if no.is_nan() {
-		"$NAN".to_string()
+		format!("{}NAN", symbol)
 	} else if no == 0.0 {
-		"$0".to_string()
+		format!("{}0", symbol)
 	} else if no.is_sign_negative() {
-		format!("(${})", shorten_abs(no))
+		format!("({}{})", symbol, shorten_abs(no))
+	} else {
+		format!("{}{}", symbol, shorten_abs(no))
+	}
+}
+
+pub fn full_dollars(symbol: &str, no: f64) -> String {
+	if no.is_nan() {
+		format!("{}NAN", symbol)
+	} else if no.is_sign_negative() && no != 0.0 {
+		format!("({}{:.2})", symbol, no.abs())
 	} else {
-		format!("${}", shorten_abs(no))
+		format!("{}{:.2}", symbol, no.abs())
 	}
 }
 
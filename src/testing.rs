@@ -0,0 +1,64 @@
+//! Fixtures shared by the unit tests.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{Custodian, ShareCount};
+use crate::asset_tag::AssetTag;
+use crate::lot::Lot;
+use crate::pot::{FolderPot, Pot};
+
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+/// A fresh directory under the system temp dir, removed along with its contents on drop.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+	pub fn new(name: &str) -> Self {
+		let unique = NEXT_DIR.fetch_add(1, Ordering::SeqCst);
+		let path = std::env::temp_dir().join(format!("pot-{}-{}-{}", name, std::process::id(), unique));
+		let _ = std::fs::remove_dir_all(&path);
+		std::fs::create_dir_all(&path).unwrap();
+		TempDir(path)
+	}
+
+	pub fn path(&self) -> &Path { &self.0 }
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_dir_all(&self.0);
+	}
+}
+
+/// An initialized folder pot that lives as long as the returned directory.
+pub fn temp_pot(name: &str) -> (TempDir, FolderPot) {
+	let dir = TempDir::new(name);
+	let mut pot = FolderPot::from_path(dir.path());
+	pot.init().unwrap();
+	(dir, pot)
+}
+
+pub fn lot(custodian: &str, symbol: &str, shares: f64) -> Lot {
+	Lot {
+		custodian: Custodian(custodian.to_string()),
+		asset_tag: AssetTag::from(symbol),
+		share_count: ShareCount(shares),
+		uid: Lot::random_uid(),
+		basis: None,
+		acquired: None,
+	}
+}
+
+/// Creates sub-pot `name` holding `lots` and records it in the parent the way `add-subpot` does.
+pub fn add_subpot(pot: &mut FolderPot, name: &str, lots: Vec<Lot>) -> FolderPot {
+	let mut subpot = *pot.subpot(name);
+	std::fs::create_dir_all(subpot.path()).unwrap();
+	subpot.init().unwrap();
+	subpot.write_lots(&lots).unwrap();
+	let tag = format!(":{}", name);
+	let mut parent_lots = pot.read_lots().unwrap();
+	parent_lots.push(lot(&tag, &tag, 1.0));
+	pot.write_lots(&parent_lots).unwrap();
+	subpot
+}
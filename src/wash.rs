@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
+
+use crate::asset_tag::AssetTag;
+use crate::lot::Lot;
+use crate::sale::Sale;
+
+pub const WINDOW_DAYS: i64 = 30;
+
+#[derive(Clone, Debug)]
+pub struct Purchase {
+	pub uid: u64,
+	pub custodian: String,
+	pub asset_tag: AssetTag,
+	pub count: f64,
+	pub acquired: NaiveDate,
+}
+
+impl Purchase {
+	pub fn from_lots_and_sales(lots: &[Lot], sales: &[Sale]) -> Vec<Purchase> {
+		let held = lots.iter().filter_map(|lot| lot.acquired.map(|acquired| Purchase {
+			uid: lot.uid,
+			custodian: lot.custodian.as_str().to_string(),
+			asset_tag: lot.asset_tag.clone(),
+			count: lot.share_count.as_f64(),
+			acquired,
+		}));
+		let sold = sales.iter().filter_map(|sale| sale.acquired.map(|acquired| Purchase {
+			uid: sale.uid,
+			custodian: sale.custodian.as_str().to_string(),
+			asset_tag: sale.asset_tag.clone(),
+			count: sale.count,
+			acquired,
+		}));
+		held.chain(sold).collect()
+	}
+	fn is_part_of(&self, sale: &Sale) -> bool {
+		self.uid == sale.uid || (
+			self.custodian == sale.custodian.as_str()
+				&& self.asset_tag == sale.asset_tag
+				&& Some(self.acquired) == sale.acquired
+		)
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct WashSale {
+	pub sale: Sale,
+	pub replacement_uid: u64,
+	pub replacement_count: f64,
+	pub disallowed: f64,
+}
+
+pub fn in_window(date: NaiveDate, around: NaiveDate) -> bool {
+	(date - around).num_days().abs() <= WINDOW_DAYS
+}
+
+pub fn detect(sales: &[Sale], purchases: &[Purchase]) -> Vec<WashSale> {
+	let mut losses = sales.iter().filter(|sale| sale.gain().unwrap_or(0.0) < 0.0).collect::<Vec<_>>();
+	losses.sort_by_key(|sale| sale.sold);
+	let mut used: HashMap<u64, f64> = HashMap::new();
+	let mut washes = Vec::new();
+	for sale in losses {
+		let loss = -sale.gain().unwrap_or(0.0);
+		let mut unmatched = sale.count;
+		let mut candidates = purchases.iter()
+			.filter(|purchase| purchase.asset_tag == sale.asset_tag && !purchase.is_part_of(sale))
+			.filter(|purchase| in_window(purchase.acquired, sale.sold))
+			.collect::<Vec<_>>();
+		candidates.sort_by_key(|purchase| purchase.acquired);
+		for purchase in candidates {
+			let available = purchase.count - used.get(&purchase.uid).cloned().unwrap_or(0.0);
+			let count = available.min(unmatched);
+			if count <= 0.0 {
+				continue;
+			}
+			*used.entry(purchase.uid).or_insert(0.0) += count;
+			unmatched -= count;
+			washes.push(WashSale {
+				sale: sale.clone(),
+				replacement_uid: purchase.uid,
+				replacement_count: count,
+				disallowed: loss * count / sale.count,
+			});
+			if unmatched <= 0.0 {
+				break;
+			}
+		}
+	}
+	washes
+}
+
+// Lots without an acquisition date never become purchases, so a wash sale against
+// one of them cannot be ruled out.
+pub fn undated_lots(lots: &[Lot], asset: &AssetTag) -> Vec<u64> {
+	lots.iter()
+		.filter(|lot| &lot.asset_tag == asset && lot.acquired.is_none())
+		.map(|lot| lot.uid)
+		.collect()
+}
+
+pub fn recent_loss<'a>(sales: &'a [Sale], asset: &AssetTag, date: NaiveDate) -> Option<&'a Sale> {
+	sales.iter()
+		.filter(|sale| &sale.asset_tag == asset && sale.gain().unwrap_or(0.0) < 0.0)
+		.filter(|sale| sale.sold <= date && date - sale.sold <= Duration::days(WINDOW_DAYS))
+		.max_by_key(|sale| sale.sold)
+}
+
+pub fn recent_purchase<'a>(purchases: &'a [Purchase], asset: &AssetTag, date: NaiveDate) -> Option<&'a Purchase> {
+	purchases.iter()
+		.filter(|purchase| &purchase.asset_tag == asset)
+		.filter(|purchase| purchase.acquired <= date && date - purchase.acquired <= Duration::days(WINDOW_DAYS))
+		.max_by_key(|purchase| purchase.acquired)
+}
+
+#[cfg(test)]
+mod tests {
+	use chrono::NaiveDate;
+
+	use crate::{Custodian, ShareCount};
+	use crate::asset_tag::AssetTag;
+	use crate::lot::Lot;
+	use crate::sale::Sale;
+	use crate::wash::{detect, Purchase, undated_lots};
+
+	fn date(month: u32, day: u32) -> NaiveDate { NaiveDate::from_ymd_opt(2025, month, day).unwrap() }
+
+	#[test]
+	fn flags_replacement_buys_in_other_custodians() {
+		let vti = AssetTag::equity("vti");
+		let sales = [Sale {
+			uid: 1,
+			custodian: Custodian("taxable".to_string()),
+			asset_tag: vti.clone(),
+			count: 10.0,
+			acquired: Some(date(1, 2)),
+			sold: date(3, 1),
+			proceeds: 900.0,
+			basis: Some(1000.0),
+		}];
+		let lot = |uid, custodian: &str, count, acquired| Lot {
+			custodian: Custodian(custodian.to_string()),
+			asset_tag: vti.clone(),
+			share_count: ShareCount(count),
+			uid,
+			basis: Some(count * 90.0),
+			acquired: Some(acquired),
+		};
+		let lots = [lot(2, "ira", 4.0, date(3, 20)), lot(3, "taxable", 10.0, date(5, 1)), lot(4, "taxable", 5.0, date(1, 2))];
+		let washes = detect(&sales, &Purchase::from_lots_and_sales(&lots, &sales));
+		assert_eq!(washes.len(), 1);
+		assert_eq!(washes[0].replacement_uid, 2);
+		assert_eq!(washes[0].disallowed, 40.0);
+		let undated = Lot { acquired: None, ..lot(5, "ira", 2.0, date(3, 2)) };
+		let lots = [lots[0].clone(), undated];
+		assert_eq!(Purchase::from_lots_and_sales(&lots, &sales).len(), 2);
+		assert_eq!(undated_lots(&lots, &vti), vec![5]);
+	}
+}